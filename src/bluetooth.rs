@@ -1,3 +1,5 @@
 mod discover;
+mod event;
 
-pub use discover::{discover, Device};
+pub use discover::{Bluez, Device};
+pub use event::DeviceEvent;
@@ -1,13 +1,38 @@
 use bluer::DeviceProperty;
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
-use async_stream::try_stream;
-use bluer::{AdapterEvent, Address, AddressType, Modalias, Uuid};
-use futures_util::stream::Stream;
+use ::prometheus::{opts, register_int_counter, IntCounter};
+use async_stream::{stream, try_stream};
+use bluer::monitor::{self, Monitor, MonitorEvent, Pattern, RssiSamplingPeriod};
+use bluer::{
+    Adapter, AdapterEvent, Address, AddressType, DiscoveryFilter, DiscoveryTransport, Modalias,
+    SessionEvent, Uuid,
+};
+use dbus::message::MatchRule;
+use futures_util::pin_mut;
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio_stream::StreamMap;
+
+use super::DeviceEvent;
+use crate::config;
+use crate::device_source;
+
+const BLUEZ_NAME: &str = "org.bluez";
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref RESTARTS: IntCounter = register_int_counter!(opts!(
+        "bluetooth_discovery_restarts_total",
+        "The number of times bluetooth discovery was restarted after a failure.",
+    ))
+    .unwrap();
+}
 
 #[derive(Debug, Error)]
 pub enum DiscoveryError {
@@ -15,11 +40,19 @@ pub enum DiscoveryError {
     Bluetooth(#[from] bluer::Error),
     #[error("system time error: {0}")]
     SystemTime(#[from] std::time::SystemTimeError),
+    #[error("D-Bus error: {0}")]
+    Dbus(#[from] dbus::Error),
+    #[error("D-Bus connection lost: {0}")]
+    DbusConnection(String),
+    #[error("bluetoothd restarted")]
+    BluezRestarted,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Device {
     pub timestamp: SystemTime,
+    pub adapter: String,
+    pub adapter_address: Address,
     pub address: Address,
     pub address_type: Option<AddressType>,
     pub name: Option<String>,
@@ -48,6 +81,8 @@ impl Default for Device {
     fn default() -> Self {
         Device {
             timestamp: SystemTime::now(),
+            adapter: String::new(),
+            adapter_address: Address::default(),
             address: Address::default(),
             address_type: None,
             name: None,
@@ -74,66 +109,433 @@ impl Default for Device {
     }
 }
 
+impl Device {
+    /// Merge a single changed property into the device.
+    pub fn apply(&mut self, property: DeviceProperty) {
+        match property {
+            DeviceProperty::Name(name) => self.name = Some(name),
+            DeviceProperty::AddressType(address_type) => self.address_type = Some(address_type),
+            DeviceProperty::Class(class) => self.class = Some(class),
+            DeviceProperty::Appearance(appearance) => self.appearance = Some(appearance),
+            DeviceProperty::Blocked(blocked) => self.blocked = blocked,
+            DeviceProperty::Paired(paired) => self.paired = paired,
+            DeviceProperty::Connected(connected) => self.connected = connected,
+            DeviceProperty::Trusted(trusted) => self.trusted = trusted,
+            DeviceProperty::Alias(alias) => self.alias = Some(alias),
+            DeviceProperty::Modalias(modalias) => self.modalias = Some(modalias),
+            DeviceProperty::Icon(icon) => self.icon = Some(icon),
+            DeviceProperty::Uuids(uuids) => self.uuids = uuids,
+            DeviceProperty::ManufacturerData(manufacturer_data) => {
+                self.manufacturer_data = manufacturer_data;
+            }
+            DeviceProperty::AdvertisingData(advertising_data) => {
+                self.advertising_data = advertising_data;
+            }
+            DeviceProperty::AdvertisingFlags(advertising_flags) => {
+                self.advertising_flags = advertising_flags
+            }
+            DeviceProperty::Rssi(rssi) => self.rssi = Some(rssi),
+            DeviceProperty::TxPower(tx_power) => self.tx_power = Some(tx_power),
+            DeviceProperty::WakeAllowed(wake_allowed) => self.wake_allowed = wake_allowed,
+            DeviceProperty::LegacyPairing(legacy_pairing) => self.legacy_pairing = legacy_pairing,
+            DeviceProperty::ServicesResolved(services_resolved) => {
+                self.services_resolved = services_resolved
+            }
+            DeviceProperty::ServiceData(service_data) => {
+                self.service_data = service_data;
+            }
+            _ => {}
+        }
+    }
+}
+
 impl From<Vec<DeviceProperty>> for Device {
     fn from(properties: Vec<DeviceProperty>) -> Self {
         let mut device = Device::default();
         for property in properties {
-            match property {
-                DeviceProperty::Name(name) => device.name = Some(name),
-                DeviceProperty::AddressType(address_type) => {
-                    device.address_type = Some(address_type)
-                }
-                DeviceProperty::Class(class) => device.class = Some(class),
-                DeviceProperty::Appearance(appearance) => device.appearance = Some(appearance),
-                DeviceProperty::Blocked(blocked) => device.blocked = blocked,
-                DeviceProperty::Paired(paired) => device.paired = paired,
-                DeviceProperty::Connected(connected) => device.connected = connected,
-                DeviceProperty::Trusted(trusted) => device.trusted = trusted,
-                DeviceProperty::Alias(alias) => device.alias = Some(alias),
-                DeviceProperty::Modalias(modalias) => device.modalias = Some(modalias),
-                DeviceProperty::Icon(icon) => device.icon = Some(icon),
-                DeviceProperty::Uuids(uuids) => device.uuids = uuids,
-                DeviceProperty::ManufacturerData(manufacturer_data) => {
-                    device.manufacturer_data = manufacturer_data;
-                }
-                DeviceProperty::AdvertisingData(advertising_data) => {
-                    device.advertising_data = advertising_data;
+            device.apply(property);
+        }
+        device
+    }
+}
+
+impl From<&config::DiscoveryFilter> for DiscoveryFilter {
+    fn from(filter: &config::DiscoveryFilter) -> Self {
+        let default = DiscoveryFilter::default();
+        DiscoveryFilter {
+            transport: match filter.transport {
+                Some(config::Transport::Auto) | None => DiscoveryTransport::Auto,
+                Some(config::Transport::Bredr) => DiscoveryTransport::BrEdr,
+                Some(config::Transport::Le) => DiscoveryTransport::Le,
+            },
+            rssi: filter.rssi,
+            pathloss: filter.pathloss,
+            uuids: filter.uuids.clone(),
+            duplicate_data: filter.duplicate_data.unwrap_or(default.duplicate_data),
+            discoverable: filter.discoverable.unwrap_or(default.discoverable),
+            ..default
+        }
+    }
+}
+
+impl From<&config::Monitor> for Monitor {
+    fn from(monitor: &config::Monitor) -> Self {
+        Monitor {
+            monitor_type: monitor::Type::OrPatterns,
+            rssi_low_threshold: monitor.rssi_low_threshold,
+            rssi_high_threshold: monitor.rssi_high_threshold,
+            rssi_low_timeout: monitor.rssi_low_timeout.map(Duration::from_secs),
+            rssi_high_timeout: monitor.rssi_high_timeout.map(Duration::from_secs),
+            rssi_sampling_period: monitor.rssi_sampling_period.map(|period| match period {
+                0 => RssiSamplingPeriod::All,
+                period => RssiSamplingPeriod::Period(Duration::from_millis(period)),
+            }),
+            patterns: Some(
+                monitor
+                    .patterns
+                    .iter()
+                    .map(|pattern| {
+                        Pattern::new(pattern.data_type, pattern.start_position, &pattern.content)
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+enum SessionOrDevice {
+    Session(SessionEvent),
+    /// The owner of the BlueZ bus name changed, bluetoothd stopped or started.
+    BluezRestarted,
+    Disconnected(String),
+    Device(Box<Result<DeviceEvent, DiscoveryError>>),
+}
+
+enum Event {
+    Adapter(AdapterEvent),
+    Device(Address, bluer::DeviceEvent),
+    Lost(Address),
+    Tick,
+}
+
+/// Device source backed by BlueZ discovery.
+#[derive(Clone, Debug)]
+pub struct Bluez {
+    config: config::Bluetooth,
+}
+
+impl Bluez {
+    pub fn new(config: config::Bluetooth) -> Self {
+        Self { config }
+    }
+}
+
+impl device_source::DeviceSource for Bluez {
+    fn events(&self) -> BoxStream<'static, DeviceEvent> {
+        discover(self.config.clone()).boxed()
+    }
+}
+
+/// Discover devices on the configured adapters.
+///
+/// The BlueZ session is supervised: when it fails, e.g. because bluetoothd
+/// restarted, it is re-created after an exponential backoff.
+fn discover(config: config::Bluetooth) -> impl Stream<Item = DeviceEvent> {
+    stream! {
+        let mut backoff = MIN_BACKOFF;
+        let mut present: HashMap<(String, Address), Device> = HashMap::new();
+        loop {
+            let events = discover_session(config.clone());
+            pin_mut!(events);
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(event) => {
+                        backoff = MIN_BACKOFF;
+                        track(&mut present, &event);
+                        yield event;
+                    }
+                    Err(e) => {
+                        log::error!("Discovery error: {}", e);
+                        break;
+                    }
                 }
-                DeviceProperty::AdvertisingFlags(advertising_flags) => {
-                    device.advertising_flags = advertising_flags
+            }
+
+            // devices will be added again once the session is back
+            for (_, device) in present.drain() {
+                yield DeviceEvent::Lost(gone_now(&device));
+            }
+
+            RESTARTS.inc();
+            log::warn!("Restarting discovery in {:?}", backoff);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+/// The device of a gone event, which happens now rather than when the device was last seen.
+fn gone_now(device: &Device) -> Device {
+    Device {
+        timestamp: SystemTime::now(),
+        ..device.clone()
+    }
+}
+
+/// Keep the devices that are present up to date with `event`.
+fn track(present: &mut HashMap<(String, Address), Device>, event: &DeviceEvent) {
+    let device = event.device();
+    let key = (device.adapter.clone(), device.address);
+    if event.is_gone() {
+        present.remove(&key);
+    } else {
+        present.insert(key, device.clone());
+    }
+}
+
+fn discover_session(
+    config: config::Bluetooth,
+) -> impl Stream<Item = Result<DeviceEvent, DiscoveryError>> {
+    try_stream! {
+        let session = bluer::Session::new().await?;
+        let session_events = session.events().await?;
+        pin_mut!(session_events);
+
+        // bluetoothd may exit without removing its adapters, so watch who owns its
+        // bus name. bluer keeps its connection private, so this needs one of its own.
+        let (bus, connection) = tokio::task::spawn_blocking(dbus_tokio::connection::new_system_sync)
+            .await
+            .map_err(|err| DiscoveryError::DbusConnection(err.to_string()))??;
+        pin_mut!(bus);
+        let rule = MatchRule::new_signal("org.freedesktop.DBus", "NameOwnerChanged")
+            .with_sender("org.freedesktop.DBus");
+        // replies only arrive while the connection is polled
+        let owner_match = tokio::select! {
+            owner_match = connection.add_match(rule) => owner_match.map_err(DiscoveryError::from),
+            err = &mut bus => Err(DiscoveryError::DbusConnection(err.to_string())),
+        }?;
+        let (_owner_match, owner_changes) = owner_match.stream::<(String, String, String)>();
+        let owner_changes = owner_changes.filter(|(_, (name, _, _))| {
+            let bluez = name == BLUEZ_NAME;
+            async move { bluez }
+        });
+        pin_mut!(owner_changes);
+
+        let mut streams = StreamMap::new();
+        for adapter in adapters(&session, &config).await? {
+            log::info!("Scanning on adapter {}", adapter.name());
+            let name = adapter.name().to_string();
+            streams.insert(name, discover_adapter(adapter, config.clone()).boxed());
+        }
+        if streams.is_empty() {
+            log::warn!("No bluetooth adapter available, waiting for one to be added");
+        }
+        let mut present = HashMap::new();
+
+        loop {
+            let event = tokio::select! {
+                event = session_events.next() => match event {
+                    Some(event) => SessionOrDevice::Session(event),
+                    None => break,
+                },
+                Some(_) = owner_changes.next() => SessionOrDevice::BluezRestarted,
+                err = &mut bus => SessionOrDevice::Disconnected(err.to_string()),
+                Some((_, event)) = streams.next(), if !streams.is_empty() => {
+                    SessionOrDevice::Device(Box::new(event))
                 }
-                DeviceProperty::Rssi(rssi) => device.rssi = Some(rssi),
-                DeviceProperty::TxPower(tx_power) => device.tx_power = Some(tx_power),
-                DeviceProperty::WakeAllowed(wake_allowed) => device.wake_allowed = wake_allowed,
-                DeviceProperty::LegacyPairing(legacy_pairing) => {
-                    device.legacy_pairing = legacy_pairing
+            };
+
+            match event {
+                SessionOrDevice::Session(SessionEvent::AdapterAdded(name)) => {
+                    if wanted(&config, &name, streams.is_empty()) {
+                        log::info!("Adapter {} added, scanning", name);
+                        let adapter = session.adapter(&name)?;
+                        streams.insert(name, discover_adapter(adapter, config.clone()).boxed());
+                    }
                 }
-                DeviceProperty::ServicesResolved(services_resolved) => {
-                    device.services_resolved = services_resolved
+                SessionOrDevice::Session(SessionEvent::AdapterRemoved(name)) => {
+                    log::warn!("Adapter {} removed", name);
+                    if streams.remove(&name).is_some() {
+                        let gone: Vec<_> = present
+                            .keys()
+                            .filter(|(adapter, _)| *adapter == name)
+                            .cloned()
+                            .collect();
+                        for key in gone {
+                            if let Some(device) = present.remove(&key) {
+                                yield DeviceEvent::Lost(gone_now(&device));
+                            }
+                        }
+                    }
                 }
-                DeviceProperty::ServiceData(service_data) => {
-                    device.service_data = service_data;
+                SessionOrDevice::Session(_) => {}
+                SessionOrDevice::BluezRestarted => Err(DiscoveryError::BluezRestarted)?,
+                SessionOrDevice::Disconnected(err) => Err(DiscoveryError::DbusConnection(err))?,
+                SessionOrDevice::Device(event) => {
+                    let event = (*event)?;
+                    track(&mut present, &event);
+                    yield event;
                 }
-                _ => {}
             }
         }
-        device
     }
 }
 
-pub fn discover() -> impl Stream<Item = Result<Device, DiscoveryError>> {
+/// Resolve the present adapters to scan on from the `[bluetooth]` config.
+async fn adapters(
+    session: &bluer::Session,
+    config: &config::Bluetooth,
+) -> Result<Vec<Adapter>, DiscoveryError> {
+    if !config.all_adapters && config.adapters.is_empty() {
+        return match session.default_adapter().await {
+            Ok(adapter) => Ok(vec![adapter]),
+            Err(e) if e.kind == bluer::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    let names = session.adapter_names().await?;
+    Ok(names
+        .iter()
+        .filter(|name| wanted(config, name, false))
+        .map(|name| session.adapter(name))
+        .collect::<Result<_, _>>()?)
+}
+
+/// Whether an adapter that was added should be scanned on. Without an adapter
+/// list the first adapter to appear is used when none is being scanned.
+fn wanted(config: &config::Bluetooth, name: &str, idle: bool) -> bool {
+    if config.all_adapters {
+        return true;
+    }
+    if config.adapters.is_empty() {
+        return idle;
+    }
+    config.adapters.iter().any(|adapter| adapter == name)
+}
+
+fn discover_adapter(
+    adapter: Adapter,
+    config: config::Bluetooth,
+) -> impl Stream<Item = Result<DeviceEvent, DiscoveryError>> {
+    let timeout = Duration::from_secs(config.timeout);
+
     try_stream! {
-        let session = bluer::Session::new().await?;
-        let adapter = session.default_adapter().await?;
         adapter.set_powered(true).await?;
-        let device_events = adapter.discover_devices_with_changes().await?;
-        for await device_event in device_events {
-            if let AdapterEvent::DeviceAdded(addr) = device_event {
-                let device = adapter.device(addr)?;
-                let properties = device.all_properties().await?;
-                let mut result = Device::from(properties);
-                result.address = device.address();
-                yield result;
+        if let Some(filter) = &config.filter {
+            adapter.set_discovery_filter(filter.into()).await?;
+        }
+        let adapter_name = adapter.name().to_string();
+        let adapter_address = adapter.address().await?;
+        // in passive mode devices come from the advertisement monitor, not a discovery session
+        let (_monitor, adapter_events, monitor_events) = match &config.monitor {
+            Some(monitor) => {
+                log::info!("Monitoring advertisements passively on adapter {}", adapter_name);
+                let manager = adapter.monitor().await?;
+                let handle = manager.register(monitor.into()).await?;
+                let events = handle.filter_map(|event| async move {
+                    match event {
+                        MonitorEvent::DeviceFound(id) => {
+                            Some(Event::Adapter(AdapterEvent::DeviceAdded(id.device)))
+                        }
+                        MonitorEvent::DeviceLost(id) => Some(Event::Lost(id.device)),
+                        _ => None,
+                    }
+                });
+                (Some(manager), adapter.events().await?.boxed(), events.boxed())
+            }
+            None => (
+                None,
+                adapter.discover_devices().await?.boxed(),
+                stream::pending().boxed(),
+            ),
+        };
+        pin_mut!(adapter_events);
+        pin_mut!(monitor_events);
+
+        let mut devices: HashMap<Address, Device> = HashMap::new();
+        let mut lost: HashSet<Address> = HashSet::new();
+        // bluer's device events never end, so they are dropped with the device
+        let mut device_events = StreamMap::new();
+        let mut ticker = tokio::time::interval(
+            timeout.clamp(Duration::from_secs(1), Duration::from_secs(10)),
+        );
+
+        loop {
+            let event = tokio::select! {
+                event = adapter_events.next() => match event {
+                    Some(event) => Event::Adapter(event),
+                    None => break,
+                },
+                Some((addr, event)) = device_events.next(), if !device_events.is_empty() => {
+                    Event::Device(addr, event)
+                },
+                Some(event) = monitor_events.next() => event,
+                _ = ticker.tick() => Event::Tick,
+            };
+
+            match event {
+                Event::Adapter(AdapterEvent::DeviceAdded(addr)) => {
+                    let device = adapter.device(addr)?;
+                    let known = devices.contains_key(&addr);
+                    if !known {
+                        device_events.insert(addr, device.events().await?.boxed());
+                    }
+                    let properties = device.all_properties().await?;
+                    let mut result = Device::from(properties);
+                    result.address = device.address();
+                    result.adapter = adapter_name.clone();
+                    result.adapter_address = adapter_address;
+                    devices.insert(addr, result.clone());
+                    if !known || lost.remove(&addr) {
+                        yield DeviceEvent::Added(result);
+                    } else {
+                        yield DeviceEvent::Updated(result);
+                    }
+                }
+                Event::Adapter(AdapterEvent::DeviceRemoved(addr)) => {
+                    lost.remove(&addr);
+                    device_events.remove(&addr);
+                    if let Some(device) = devices.remove(&addr) {
+                        yield DeviceEvent::Removed(gone_now(&device));
+                    }
+                }
+                Event::Adapter(_) => {}
+                Event::Lost(addr) => {
+                    if let Some(device) = devices.get(&addr) {
+                        if lost.insert(addr) {
+                            yield DeviceEvent::Lost(gone_now(device));
+                        }
+                    }
+                }
+                Event::Device(addr, bluer::DeviceEvent::PropertyChanged(property)) => {
+                    if let Some(device) = devices.get_mut(&addr) {
+                        device.timestamp = SystemTime::now();
+                        device.apply(property);
+                        if lost.remove(&addr) {
+                            yield DeviceEvent::Added(device.clone());
+                        } else {
+                            yield DeviceEvent::Updated(device.clone());
+                        }
+                    }
+                }
+                Event::Tick => {
+                    let now = SystemTime::now();
+                    for (addr, device) in devices.iter() {
+                        let elapsed = now.duration_since(device.timestamp).unwrap_or_default();
+                        if elapsed >= timeout && lost.insert(*addr) {
+                            yield DeviceEvent::Lost(gone_now(device));
+                        }
+                    }
+                }
+            }
+        }
+
+        // the adapter is gone, and so are the devices it was tracking
+        for (addr, device) in devices {
+            if !lost.contains(&addr) {
+                yield DeviceEvent::Lost(gone_now(&device));
             }
         }
     }
@@ -0,0 +1,39 @@
+use super::Device;
+
+/// A change in the lifecycle of a discovered device.
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    /// The device was seen for the first time, or again after being lost.
+    Added(Device),
+    /// One or more properties of a known device changed.
+    Updated(Device),
+    /// The device has not been seen within the configured timeout.
+    Lost(Device),
+    /// BlueZ removed the device.
+    Removed(Device),
+}
+
+impl DeviceEvent {
+    pub fn device(&self) -> &Device {
+        match self {
+            Self::Added(device)
+            | Self::Updated(device)
+            | Self::Lost(device)
+            | Self::Removed(device) => device,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Added(_) => "added",
+            Self::Updated(_) => "updated",
+            Self::Lost(_) => "lost",
+            Self::Removed(_) => "removed",
+        }
+    }
+
+    /// Whether the device is no longer present.
+    pub fn is_gone(&self) -> bool {
+        matches!(self, Self::Lost(_) | Self::Removed(_))
+    }
+}
@@ -0,0 +1,5 @@
+mod capture;
+mod hci;
+mod reader;
+
+pub use capture::Capture;
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use async_stream::stream;
+use bluer::Address;
+use futures_util::stream::{BoxStream, StreamExt};
+use tokio::fs::File;
+use tokio::io::BufReader;
+
+use super::hci;
+use super::reader::{Datalink, Reader};
+use crate::bluetooth::{Device, DeviceEvent};
+use crate::config;
+use crate::device_source;
+
+const H4_EVENT: u8 = 0x04;
+const MONITOR_NEW_INDEX: u32 = 0x0000;
+const MONITOR_EVENT: u32 = 0x0003;
+
+/// Device source reading LE advertising reports from a btsnoop capture, such
+/// as the ones written by `btmon -w`.
+#[derive(Clone, Debug)]
+pub struct Capture {
+    config: config::Btsnoop,
+}
+
+impl Capture {
+    pub fn new(config: config::Btsnoop) -> Self {
+        Self { config }
+    }
+}
+
+impl device_source::DeviceSource for Capture {
+    fn events(&self) -> BoxStream<'static, DeviceEvent> {
+        let path = self.config.path.clone();
+        let speed = self.config.speed.unwrap_or(1.0);
+
+        stream! {
+            let file = match File::open(&path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    log::error!("failed to open capture {}: {}", path.display(), e);
+                    return;
+                }
+            };
+            let mut reader = match Reader::new(BufReader::new(file)).await {
+                Ok(reader) => reader,
+                Err(e) => {
+                    log::error!("failed to read capture {}: {}", path.display(), e);
+                    return;
+                }
+            };
+
+            let mut adapters: HashMap<u16, (String, Address)> = HashMap::new();
+            let mut devices: HashMap<(u16, Address), Device> = HashMap::new();
+            let mut previous: Option<SystemTime> = None;
+
+            loop {
+                let record = match reader.next().await {
+                    Ok(Some(record)) => record,
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::error!("failed to read capture {}: {}", path.display(), e);
+                        break;
+                    }
+                };
+
+                let (index, event) = match reader.datalink() {
+                    Datalink::H1 if record.flags & 0x03 == 0x03 => (0, &record.data[..]),
+                    Datalink::H4 if record.data.first() == Some(&H4_EVENT) => {
+                        (0, &record.data[1..])
+                    }
+                    Datalink::Monitor => {
+                        let index = (record.flags >> 16) as u16;
+                        match record.flags & 0xffff {
+                            MONITOR_EVENT => (index, &record.data[..]),
+                            MONITOR_NEW_INDEX if record.data.len() >= 16 => {
+                                // type, bus, address, name
+                                let mut address = [0u8; 6];
+                                address.copy_from_slice(&record.data[2..8]);
+                                address.reverse();
+                                adapters.insert(index, (format!("hci{}", index), Address(address)));
+                                continue;
+                            }
+                            _ => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+
+                for report in hci::advertising_reports(event) {
+                    if speed > 0.0 {
+                        let delay = previous.and_then(|previous| record.timestamp.duration_since(previous).ok());
+                        if let Some(delay) = delay {
+                            tokio::time::sleep(delay.div_f64(speed)).await;
+                        }
+                    }
+                    previous = Some(record.timestamp);
+
+                    let known = devices.contains_key(&(index, report.address));
+                    let device = devices.entry((index, report.address)).or_insert_with(|| {
+                        let (adapter, adapter_address) = adapters
+                            .get(&index)
+                            .cloned()
+                            .unwrap_or_else(|| (format!("hci{}", index), Address::any()));
+                        Device {
+                            adapter,
+                            adapter_address,
+                            ..Device::default()
+                        }
+                    });
+                    device.timestamp = record.timestamp;
+                    hci::apply(device, &report);
+
+                    if known {
+                        yield DeviceEvent::Updated(device.clone());
+                    } else {
+                        yield DeviceEvent::Added(device.clone());
+                    }
+                }
+            }
+
+            log::info!("Finished reading capture {}", path.display());
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_source::DeviceSource;
+
+    /// An LE advertising report of 11:22:33:44:55:66 at -60 dBm named "Test".
+    const ADVERTISING_REPORT: [u8; 20] = [
+        0x3e, 0x12, 0x02, 0x01, 0x00, 0x01, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x06, 0x05, 0x09,
+        b'T', b'e', b's', b't', 0xc4,
+    ];
+
+    /// Monitor new index of hci1 with address 00:11:22:33:44:55.
+    const NEW_INDEX: [u8; 16] = [
+        0x00, 0x01, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00, b'h', b'c', b'i', b'1', 0, 0, 0, 0,
+    ];
+
+    /// Read the devices of a capture of `records`.
+    async fn devices(datalink: u32, records: &[(u32, &[u8])]) -> Vec<Device> {
+        let mut capture = b"btsnoop\0\0\0\0\x01".to_vec();
+        capture.extend(datalink.to_be_bytes());
+        for (flags, data) in records {
+            let length = (data.len() as u32).to_be_bytes();
+            capture.extend(length);
+            capture.extend(length);
+            capture.extend(flags.to_be_bytes());
+            capture.extend([0; 4]);
+            capture.extend([0, 0xe2, 0xe7, 0xd7, 0x27, 0x4d, 0xc0, 0]);
+            capture.extend(*data);
+        }
+        let path = std::env::temp_dir().join(format!(
+            "capture-{}-{}.btsnoop",
+            std::process::id(),
+            datalink
+        ));
+        std::fs::write(&path, capture).unwrap();
+
+        let capture = Capture::new(config::Btsnoop {
+            path: path.clone(),
+            speed: Some(0.0),
+        });
+        let devices = capture
+            .events()
+            .map(|event| event.device().clone())
+            .collect()
+            .await;
+        std::fs::remove_file(path).unwrap();
+        devices
+    }
+
+    fn assert_test_device(devices: &[Device], adapter: &str) {
+        assert_eq!(devices.len(), 1);
+        let device = &devices[0];
+        assert_eq!(device.adapter, adapter);
+        assert_eq!(
+            device.address,
+            Address([0x11, 0x22, 0x33, 0x44, 0x55, 0x66])
+        );
+        assert_eq!(device.name.as_deref(), Some("Test"));
+        assert_eq!(device.rssi, Some(-60));
+    }
+
+    #[tokio::test]
+    async fn reads_h1_events() {
+        // received events, other packets are ignored
+        let devices = devices(
+            1001,
+            &[(0x02, &[0x03, 0x0c, 0x00]), (0x03, &ADVERTISING_REPORT)],
+        )
+        .await;
+        assert_test_device(&devices, "hci0");
+    }
+
+    #[tokio::test]
+    async fn reads_h4_events() {
+        let mut event = vec![H4_EVENT];
+        event.extend(ADVERTISING_REPORT);
+        let devices = devices(1002, &[(0x00, &[0x01, 0x03, 0x0c, 0x00]), (0x03, &event)]).await;
+        assert_test_device(&devices, "hci0");
+    }
+
+    #[tokio::test]
+    async fn reads_monitor_events() {
+        let devices = devices(
+            2001,
+            &[
+                (1 << 16 | MONITOR_NEW_INDEX, &NEW_INDEX),
+                (1 << 16 | MONITOR_EVENT, &ADVERTISING_REPORT),
+            ],
+        )
+        .await;
+        assert_test_device(&devices, "hci1");
+        let adapter_address = Address([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(devices[0].adapter_address, adapter_address);
+    }
+}
@@ -0,0 +1,248 @@
+use bluer::{Address, AddressType, Uuid, UuidExt};
+
+use crate::bluetooth::Device;
+
+const EVENT_LE_META: u8 = 0x3e;
+const SUBEVENT_ADVERTISING_REPORT: u8 = 0x02;
+const SUBEVENT_EXTENDED_ADVERTISING_REPORT: u8 = 0x0d;
+
+/// RSSI value reported when the controller could not measure it.
+const RSSI_UNAVAILABLE: i8 = 127;
+
+/// A single LE advertising report.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub address: Address,
+    pub address_type: AddressType,
+    pub rssi: Option<i16>,
+    pub data: Vec<u8>,
+}
+
+/// Extract the LE advertising reports from an HCI event packet.
+pub fn advertising_reports(event: &[u8]) -> Vec<Report> {
+    let mut reports = vec![];
+    if event.len() < 4 || event[0] != EVENT_LE_META {
+        return reports;
+    }
+
+    let params = &event[2..];
+    let count = params[1] as usize;
+    let mut rest = &params[2..];
+
+    for _ in 0..count {
+        let report = match params[0] {
+            SUBEVENT_ADVERTISING_REPORT => legacy_report(&mut rest),
+            SUBEVENT_EXTENDED_ADVERTISING_REPORT => extended_report(&mut rest),
+            _ => None,
+        };
+        match report {
+            Some(report) => reports.push(report),
+            None => break,
+        }
+    }
+    reports
+}
+
+fn legacy_report(rest: &mut &[u8]) -> Option<Report> {
+    // event type, address type, address, data length
+    let header = rest.get(..9)?;
+    let length = header[8] as usize;
+    let data = rest.get(9..9 + length)?.to_vec();
+    let rssi = *rest.get(9 + length)? as i8;
+    let report = Report {
+        address: address(&header[2..8]),
+        address_type: address_type(header[1]),
+        rssi: (rssi != RSSI_UNAVAILABLE).then_some(rssi.into()),
+        data,
+    };
+    *rest = &rest[10 + length..];
+    Some(report)
+}
+
+fn extended_report(rest: &mut &[u8]) -> Option<Report> {
+    // event type, address type, address, phys, sid, tx power, rssi, interval,
+    // direct address type, direct address, data length
+    let header = rest.get(..24)?;
+    let length = header[23] as usize;
+    let data = rest.get(24..24 + length)?.to_vec();
+    let rssi = header[13] as i8;
+    let report = Report {
+        address: address(&header[3..9]),
+        address_type: address_type(header[2]),
+        rssi: (rssi != RSSI_UNAVAILABLE).then_some(rssi.into()),
+        data,
+    };
+    *rest = &rest[24 + length..];
+    Some(report)
+}
+
+fn address(bytes: &[u8]) -> Address {
+    let mut address = [0u8; 6];
+    address.copy_from_slice(bytes);
+    address.reverse();
+    Address(address)
+}
+
+fn address_type(value: u8) -> AddressType {
+    match value {
+        0x00 | 0x02 => AddressType::LePublic,
+        _ => AddressType::LeRandom,
+    }
+}
+
+/// Merge an advertising report into a device, the way BlueZ merges
+/// advertisements and scan responses into a single device object.
+pub fn apply(device: &mut Device, report: &Report) {
+    device.address = report.address;
+    device.address_type = Some(report.address_type);
+    if report.rssi.is_some() {
+        device.rssi = report.rssi;
+    }
+
+    let mut data = report.data.as_slice();
+    while let Some((&length, rest)) = data.split_first() {
+        let length = length as usize;
+        if length == 0 || rest.len() < length {
+            break;
+        }
+        let (ad_type, value) = (rest[0], &rest[1..length]);
+        apply_structure(device, ad_type, value);
+        device.advertising_data.insert(ad_type, value.to_vec());
+        data = &rest[length..];
+    }
+}
+
+fn apply_structure(device: &mut Device, ad_type: u8, value: &[u8]) {
+    match ad_type {
+        0x01 => device.advertising_flags = value.to_vec(),
+        0x02 | 0x03 => device.uuids.extend(
+            value
+                .chunks_exact(2)
+                .map(|uuid| Uuid::from_u16(u16::from_le_bytes([uuid[0], uuid[1]]))),
+        ),
+        0x04 | 0x05 => device.uuids.extend(
+            value
+                .chunks_exact(4)
+                .map(|uuid| Uuid::from_u32(u32::from_le_bytes(uuid.try_into().unwrap()))),
+        ),
+        0x06 | 0x07 => device.uuids.extend(value.chunks_exact(16).map(uuid128)),
+        0x08 if device.name.is_none() => {
+            device.name = Some(String::from_utf8_lossy(value).into_owned())
+        }
+        0x09 => device.name = Some(String::from_utf8_lossy(value).into_owned()),
+        0x0a if value.len() == 1 => device.tx_power = Some((value[0] as i8).into()),
+        0x16 if value.len() >= 2 => {
+            let uuid = Uuid::from_u16(u16::from_le_bytes([value[0], value[1]]));
+            device.service_data.insert(uuid, value[2..].to_vec());
+        }
+        0x19 if value.len() == 2 => {
+            device.appearance = Some(u16::from_le_bytes([value[0], value[1]]))
+        }
+        0x20 if value.len() >= 4 => {
+            let uuid = Uuid::from_u32(u32::from_le_bytes(value[..4].try_into().unwrap()));
+            device.service_data.insert(uuid, value[4..].to_vec());
+        }
+        0x21 if value.len() >= 16 => {
+            device
+                .service_data
+                .insert(uuid128(&value[..16]), value[16..].to_vec());
+        }
+        0xff if value.len() >= 2 => {
+            let company = u16::from_le_bytes([value[0], value[1]]);
+            device
+                .manufacturer_data
+                .insert(company, value[2..].to_vec());
+        }
+        _ => {}
+    }
+}
+
+fn uuid128(bytes: &[u8]) -> Uuid {
+    let mut uuid = [0u8; 16];
+    uuid.copy_from_slice(bytes);
+    uuid.reverse();
+    Uuid::from_bytes(uuid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flags, the complete name "Test" and manufacturer data of company 0x0499.
+    const DATA: [u8; 15] = [
+        0x02, 0x01, 0x06, // flags
+        0x05, 0x09, b'T', b'e', b's', b't', // complete local name
+        0x05, 0xff, 0x99, 0x04, 0xaa, 0xbb, // manufacturer data
+    ];
+
+    /// An LE advertising report of 11:22:33:44:55:66 at -60 dBm.
+    const ADVERTISING_REPORT: [u8; 29] = [
+        0x3e, 0x1b, // LE meta event
+        0x02, 0x01, // advertising report, 1 report
+        0x00, 0x01, // ADV_IND, random address
+        0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // address
+        0x0f, // data length
+        0x02, 0x01, 0x06, 0x05, 0x09, b'T', b'e', b's', b't', 0x05, 0xff, 0x99, 0x04, 0xaa, 0xbb,
+        0xc4, // rssi
+    ];
+
+    /// An LE extended advertising report of 11:22:33:44:55:66 at -60 dBm.
+    const EXTENDED_ADVERTISING_REPORT: [u8; 43] = [
+        0x3e, 0x29, // LE meta event
+        0x0d, 0x01, // extended advertising report, 1 report
+        0x13, 0x00, // connectable, scannable, legacy
+        0x00, // public address
+        0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // address
+        0x01, 0x00, 0xff, // primary phy, secondary phy, sid
+        0x7f, // tx power not available
+        0xc4, // rssi
+        0x00, 0x00, // periodic advertising interval
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // direct address
+        0x0f, // data length
+        0x02, 0x01, 0x06, 0x05, 0x09, b'T', b'e', b's', b't', 0x05, 0xff, 0x99, 0x04, 0xaa, 0xbb,
+    ];
+
+    #[test]
+    fn parses_advertising_report() {
+        let reports = advertising_reports(&ADVERTISING_REPORT);
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(
+            report.address,
+            Address([0x11, 0x22, 0x33, 0x44, 0x55, 0x66])
+        );
+        assert_eq!(report.address_type, AddressType::LeRandom);
+        assert_eq!(report.rssi, Some(-60));
+        assert_eq!(report.data, DATA);
+    }
+
+    #[test]
+    fn parses_extended_advertising_report() {
+        let reports = advertising_reports(&EXTENDED_ADVERTISING_REPORT);
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(
+            report.address,
+            Address([0x11, 0x22, 0x33, 0x44, 0x55, 0x66])
+        );
+        assert_eq!(report.address_type, AddressType::LePublic);
+        assert_eq!(report.rssi, Some(-60));
+        assert_eq!(report.data, DATA);
+    }
+
+    #[test]
+    fn stops_at_truncated_report() {
+        let reports = advertising_reports(&ADVERTISING_REPORT[..28]);
+        assert!(reports.is_empty());
+    }
+
+    #[test]
+    fn applies_report() {
+        let mut device = Device::default();
+        apply(&mut device, &advertising_reports(&ADVERTISING_REPORT)[0]);
+        assert_eq!(device.name.as_deref(), Some("Test"));
+        assert_eq!(device.rssi, Some(-60));
+        assert_eq!(device.advertising_flags, [0x06]);
+        assert_eq!(device.manufacturer_data[&0x0499], [0xaa, 0xbb]);
+    }
+}
@@ -0,0 +1,196 @@
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+const MAGIC: &[u8; 8] = b"btsnoop\0";
+const VERSION: u32 = 1;
+
+/// Microseconds between midnight, January 1st 0 AD and the unix epoch.
+const EPOCH_DELTA: i64 = 0x00dc_ddb3_0f2f_8000;
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a btsnoop file")]
+    Magic,
+    #[error("unsupported btsnoop version {0}")]
+    Version(u32),
+    #[error("unsupported datalink type {0}")]
+    Datalink(u32),
+}
+
+/// The packet encapsulation used in a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Datalink {
+    /// Un-encapsulated HCI, the direction and packet type are in the record flags.
+    H1,
+    /// HCI UART, every packet starts with a packet type indicator.
+    H4,
+    /// Linux monitor, as written by `btmon -w`.
+    Monitor,
+}
+
+impl TryFrom<u32> for Datalink {
+    type Error = CaptureError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1001 => Ok(Self::H1),
+            1002 => Ok(Self::H4),
+            2001 => Ok(Self::Monitor),
+            other => Err(CaptureError::Datalink(other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub flags: u32,
+    pub timestamp: SystemTime,
+    pub data: Vec<u8>,
+}
+
+/// Reads packet records from a btsnoop capture.
+pub struct Reader<R> {
+    inner: R,
+    datalink: Datalink,
+}
+
+impl<R> Reader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub async fn new(mut inner: R) -> Result<Self, CaptureError> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic).await?;
+        if &magic != MAGIC {
+            return Err(CaptureError::Magic);
+        }
+
+        let version = inner.read_u32().await?;
+        if version != VERSION {
+            return Err(CaptureError::Version(version));
+        }
+
+        let datalink = Datalink::try_from(inner.read_u32().await?)?;
+        Ok(Self { inner, datalink })
+    }
+
+    pub fn datalink(&self) -> Datalink {
+        self.datalink
+    }
+
+    /// The next record, or `None` at the end of the capture. A truncated last
+    /// record, as left behind by an interrupted capture, is treated as the end.
+    pub async fn next(&mut self) -> Result<Option<Record>, CaptureError> {
+        let mut header = [0u8; 24];
+        match self.inner.read_exact(&mut header).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let included_length = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        let flags = u32::from_be_bytes(header[8..12].try_into().unwrap());
+        let timestamp = i64::from_be_bytes(header[16..24].try_into().unwrap());
+
+        let mut data = vec![0u8; included_length as usize];
+        match self.inner.read_exact(&mut data).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        let micros = timestamp.saturating_sub(EPOCH_DELTA).max(0) as u64;
+        Ok(Some(Record {
+            flags,
+            timestamp: UNIX_EPOCH + Duration::from_micros(micros),
+            data,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header of a capture written by `btmon -w`.
+    const MONITOR_HEADER: [u8; 16] = [
+        b'b', b't', b's', b'n', b'o', b'o', b'p', 0, // magic
+        0, 0, 0, 1, // version
+        0, 0, 0x07, 0xd1, // datalink 2001
+    ];
+
+    /// A record of a 4 byte packet, 2023-11-14T22:13:20Z.
+    const RECORD: [u8; 28] = [
+        0, 0, 0, 4, // original length
+        0, 0, 0, 4, // included length
+        0, 0, 0, 3, // flags
+        0, 0, 0, 0, // drops
+        0, 0xe2, 0xe7, 0xd7, 0x27, 0x4d, 0xc0, 0, // timestamp
+        0x3e, 0x02, 0x02, 0x00, // data
+    ];
+
+    fn header(datalink: u32) -> Vec<u8> {
+        let mut header = MONITOR_HEADER[..12].to_vec();
+        header.extend(datalink.to_be_bytes());
+        header
+    }
+
+    #[tokio::test]
+    async fn reads_datalinks() {
+        for (value, datalink) in [
+            (1001, Datalink::H1),
+            (1002, Datalink::H4),
+            (2001, Datalink::Monitor),
+        ] {
+            let header = header(value);
+            let reader = Reader::new(&header[..]).await.unwrap();
+            assert_eq!(reader.datalink(), datalink);
+        }
+        let reader = Reader::new(&MONITOR_HEADER[..]).await.unwrap();
+        assert_eq!(reader.datalink(), Datalink::Monitor);
+    }
+
+    #[tokio::test]
+    async fn rejects_other_files() {
+        let mut pcap = MONITOR_HEADER;
+        pcap[..4].copy_from_slice(&[0xd4, 0xc3, 0xb2, 0xa1]);
+        let result = Reader::new(&pcap[..]).await;
+        assert!(matches!(result, Err(CaptureError::Magic)));
+
+        let header = header(1003);
+        let result = Reader::new(&header[..]).await;
+        assert!(matches!(result, Err(CaptureError::Datalink(1003))));
+    }
+
+    #[tokio::test]
+    async fn reads_records() {
+        let mut capture = MONITOR_HEADER.to_vec();
+        capture.extend(RECORD);
+        let mut reader = Reader::new(&capture[..]).await.unwrap();
+
+        let record = reader.next().await.unwrap().unwrap();
+        assert_eq!(record.flags, 3);
+        assert_eq!(
+            record.timestamp,
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert_eq!(record.data, [0x3e, 0x02, 0x02, 0x00]);
+        assert!(reader.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn ends_at_truncated_record() {
+        let mut capture = MONITOR_HEADER.to_vec();
+        capture.extend(RECORD);
+        capture.extend(&RECORD[..26]);
+        let mut reader = Reader::new(&capture[..]).await.unwrap();
+
+        assert!(reader.next().await.unwrap().is_some());
+        assert!(reader.next().await.unwrap().is_none());
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use bluer::{Address, Uuid};
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
 
 lazy_static! {
     #[derive(Copy, Clone, Debug)]
@@ -27,28 +31,156 @@ lazy_static! {
         }
 
         let mut config_str = String::new();
-        config_file.unwrap().read_to_string(&mut config_str).unwrap_or_else(|_| {
-            0
-        });
-        toml::from_str(&config_str).unwrap_or_else(|_| Config::default())
+        config_file.unwrap().read_to_string(&mut config_str).unwrap_or(0);
+        // a partly applied config would silently disable the writers after the bad field
+        toml::from_str(&config_str).unwrap_or_else(|err| {
+            log::error!("failed to parse config.toml: {}", err);
+            std::process::exit(1);
+        })
     };
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
+    #[serde(default)]
+    pub bluetooth: Bluetooth,
     pub prometheus: Option<Prometheus>,
     pub loki: Option<Loki>,
+    pub record: Option<Record>,
+    pub replay: Option<Replay>,
+    pub btsnoop: Option<Btsnoop>,
+    #[serde(default)]
+    pub bthome: Bthome,
+    #[serde(default)]
+    pub xiaomi: Xiaomi,
+    #[serde(default)]
+    pub victron: Victron,
+    #[serde(default)]
+    pub identities: Identities,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            bluetooth: Bluetooth::default(),
             prometheus: Some(Prometheus::default()),
             loki: None,
+            record: None,
+            replay: None,
+            btsnoop: None,
+            bthome: Bthome::default(),
+            xiaomi: Xiaomi::default(),
+            victron: Victron::default(),
+            identities: Identities::default(),
         }
     }
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Bluetooth {
+    /// Scan on every adapter known to BlueZ.
+    pub all_adapters: bool,
+    /// Adapter names to scan on, e.g. `hci0`. Empty means the default adapter.
+    pub adapters: Vec<String>,
+    /// Seconds without an update before a device is considered lost.
+    pub timeout: u64,
+    /// Discovery filter applied to every scanned adapter.
+    #[serde(deserialize_with = "discovery_filter")]
+    pub filter: Option<DiscoveryFilter>,
+    /// Scan passively with an advertisement monitor instead of active discovery.
+    pub monitor: Option<Monitor>,
+    /// What to do with devices using non-resolvable private addresses.
+    pub non_resolvable: NonResolvable,
+}
+
+impl Default for Bluetooth {
+    fn default() -> Self {
+        Self {
+            all_adapters: false,
+            adapters: vec![],
+            timeout: 300,
+            filter: None,
+            monitor: None,
+            non_resolvable: NonResolvable::Keep,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NonResolvable {
+    /// Treat them like every other device.
+    Keep,
+    /// Ignore them.
+    Drop,
+    /// Only count them, without series per address.
+    Aggregate,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    Auto,
+    Bredr,
+    Le,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DiscoveryFilter {
+    pub transport: Option<Transport>,
+    /// Only report devices with an RSSI above this threshold.
+    pub rssi: Option<i16>,
+    /// Only report devices with a pathloss below this threshold.
+    pub pathloss: Option<u16>,
+    /// Only report devices advertising any of these service UUIDs.
+    pub uuids: HashSet<Uuid>,
+    pub duplicate_data: Option<bool>,
+    pub discoverable: Option<bool>,
+}
+
+/// BlueZ rejects filters with both an RSSI and a pathloss threshold, so reject them up front.
+fn discovery_filter<'de, D>(deserializer: D) -> Result<Option<DiscoveryFilter>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let filter = Option::<DiscoveryFilter>::deserialize(deserializer)?;
+    if let Some(DiscoveryFilter {
+        rssi: Some(_),
+        pathloss: Some(_),
+        ..
+    }) = filter
+    {
+        return Err(D::Error::custom(
+            "bluetooth.filter can have either rssi or pathloss, not both",
+        ));
+    }
+    Ok(filter)
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Monitor {
+    pub rssi_low_threshold: Option<i16>,
+    pub rssi_high_threshold: Option<i16>,
+    /// Seconds below the low threshold before a device is lost.
+    pub rssi_low_timeout: Option<u64>,
+    /// Seconds above the high threshold before a device is found.
+    pub rssi_high_timeout: Option<u64>,
+    /// Milliseconds to group advertisements for, `0` reports every advertisement.
+    pub rssi_sampling_period: Option<u64>,
+    /// Devices matching any of the patterns are reported.
+    pub patterns: Vec<MonitorPattern>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MonitorPattern {
+    pub data_type: u8,
+    #[serde(default)]
+    pub start_position: u8,
+    pub content: Vec<u8>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Prometheus {
     pub exporter: Option<PrometheusExporter>,
@@ -90,3 +222,83 @@ pub struct Loki {
     pub username: Option<String>,
     pub password: Option<String>,
 }
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Record {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Replay {
+    pub path: PathBuf,
+    /// Playback speed multiplier, `0` replays as fast as possible. Defaults to `1`.
+    pub speed: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Btsnoop {
+    pub path: PathBuf,
+    /// Playback speed multiplier, `0` reads as fast as possible. Defaults to `1`.
+    pub speed: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Bthome {
+    /// Encryption keys of BTHome devices that send encrypted advertisements.
+    pub keys: HashMap<Address, Key>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Xiaomi {
+    /// Bindkeys of MiBeacon devices that send encrypted advertisements.
+    pub bindkeys: HashMap<Address, Key>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Victron {
+    /// Advertisement keys of Victron devices, as shown by VictronConnect.
+    pub keys: HashMap<Address, Key>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Identities {
+    /// Identity Resolving Keys by the name of their device, in the byte order BlueZ stores them.
+    pub keys: HashMap<String, Key>,
+    /// Also use the keys of the devices paired with BlueZ, read from `/var/lib/bluetooth`.
+    pub import_bluez: bool,
+}
+
+/// A 128-bit AES key, written as 32 hex digits.
+#[derive(Debug, Clone, Copy)]
+pub struct Key(pub [u8; 16]);
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        if hex.len() != 32 || !hex.is_ascii() {
+            return Err("expected a key of 32 hex digits".to_owned());
+        }
+        let mut key = [0; 16];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte =
+                u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|err| err.to_string())?;
+        }
+        Ok(Key(key))
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
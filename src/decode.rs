@@ -0,0 +1,337 @@
+mod address;
+mod advertising;
+mod apple;
+mod assigned;
+mod bthome;
+mod class;
+mod crypto;
+mod eddystone;
+mod exposure_notification;
+mod fast_pair;
+mod govee;
+mod ibeacon;
+mod identity;
+mod microsoft;
+mod reading;
+mod ruuvi;
+mod switchbot;
+mod victron;
+mod xiaomi;
+
+use std::collections::BTreeMap;
+
+use bluer::Uuid;
+use serde::Serialize;
+
+use crate::bluetooth::Device;
+use crate::config;
+
+pub use address::{aggregated, dropped, AddressKind};
+pub use advertising::Advertising;
+pub use apple::Apple;
+pub use bthome::{BtHome, SensorReading};
+pub use class::DeviceClass;
+pub use eddystone::Eddystone;
+pub use exposure_notification::ExposureNotification;
+pub use fast_pair::FastPair;
+pub use govee::Govee;
+pub use ibeacon::IBeacon;
+pub use microsoft::Microsoft;
+pub use reading::Reading;
+pub use ruuvi::Ruuvi;
+pub use switchbot::SwitchBot;
+pub use victron::Victron;
+pub use xiaomi::Xiaomi;
+
+/// Everything decoded from a device's advertisements.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Decoded {
+    pub address_kind: AddressKind,
+    /// Name of the device a resolvable private address was resolved to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Company identifier of the manufacturer specific data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer_id: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manufacturer_name: Option<String>,
+    /// E.g. `Watch: Sports Watch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appearance_name: Option<String>,
+    /// Names of the advertised services that have one.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub service_names: BTreeMap<Uuid, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_service: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<DeviceClass>,
+    pub advertising: Advertising,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ibeacon: Option<IBeacon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eddystone: Option<Eddystone>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ruuvi: Option<Ruuvi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bthome: Option<BtHome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xiaomi: Option<Xiaomi>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub govee: Option<Govee>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub switchbot: Option<SwitchBot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub victron: Option<Victron>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apple: Option<Apple>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub microsoft: Option<Microsoft>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fast_pair: Option<FastPair>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exposure_notification: Option<ExposureNotification>,
+    /// Platform or ecosystem the device belongs to, e.g. `apple` or `fast_pair`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_family: Option<&'static str>,
+}
+
+impl Decoded {
+    /// Labels contributed by all decoders.
+    pub fn labels(&self) -> Vec<(&'static str, String)> {
+        let mut labels = self.advertising.labels();
+        labels.push(("address_type", self.address_kind.as_str().to_owned()));
+        if let Some(identity) = &self.identity {
+            labels.push(("identity", identity.clone()));
+        }
+        if let Some(manufacturer) = self.manufacturer() {
+            labels.push(("manufacturer", manufacturer));
+        }
+        if let Some(device_family) = self.device_family {
+            labels.push(("device_family", device_family.to_owned()));
+        }
+        labels
+    }
+
+    /// Name of the manufacturer, or its hex company identifier if the name is unknown.
+    pub fn manufacturer(&self) -> Option<String> {
+        let id = self.manufacturer_id?;
+        Some(
+            self.manufacturer_name
+                .clone()
+                .unwrap_or_else(|| format!("{:#06x}", id)),
+        )
+    }
+
+    /// The first family recognized, by their messages or by services listed without data.
+    fn family(&self, device: &Device) -> Option<&'static str> {
+        let lists = |uuid: Uuid| device.uuids.contains(&uuid);
+        if self.apple.as_ref().is_some_and(Apple::is_apple) {
+            Some("apple")
+        } else if self.microsoft.is_some() {
+            Some("microsoft")
+        } else if self.fast_pair.is_some() || lists(fast_pair::service_uuid()) {
+            Some("fast_pair")
+        } else if self.exposure_notification.is_some()
+            || lists(exposure_notification::service_uuid())
+        {
+            Some("exposure_notification")
+        } else if self.ibeacon.is_some() {
+            Some("ibeacon")
+        } else {
+            None
+        }
+    }
+
+    /// Values of the device and all decoders to publish as metrics.
+    pub fn readings(&self, device: &Device) -> Vec<Reading> {
+        let appearance = self.advertising.appearance.and_then(assigned::appearance);
+        let primary_service = self.primary_service.map(|uuid| {
+            self.service_names
+                .get(&uuid)
+                .cloned()
+                .unwrap_or_else(|| uuid.to_string())
+        });
+        let mut readings = vec![Reading::new(
+            "bluetooth_device_info",
+            "Information about the bluetooth device, the value is always 1.",
+            "",
+            1.0,
+        )
+        .label(
+            "appearance_category",
+            appearance.map(|(category, _)| category).unwrap_or_default(),
+        )
+        .label("primary_service", primary_service.unwrap_or_default())
+        .label("device_family", self.device_family.unwrap_or_default())
+        .label("major_class", "")
+        .label("minor_class", "")
+        .labels(self.class.iter().flat_map(DeviceClass::labels).collect())
+        .single()];
+        if let Some(rssi) = device.rssi {
+            // every RSSI reading has the iBeacon labels, empty unless the device is one
+            let empty = ibeacon::LABELS.map(|name| (name, String::new()));
+            let ibeacon = self.ibeacon.iter().flat_map(IBeacon::labels);
+            readings.push(
+                Reading::new(
+                    "bluetooth_rssi",
+                    "The Received Signal Strength Indicator value for the bluetooth device.",
+                    "RSSI",
+                    rssi.into(),
+                )
+                .labels(empty.into_iter().chain(ibeacon).collect())
+                .single(),
+            );
+        }
+        if let Some(tx_power) = device.tx_power {
+            readings.push(Reading::new(
+                "bluetooth_tx_power",
+                "The TX Power in dBm for the bluetooth device.",
+                "dBm",
+                tx_power.into(),
+            ));
+        }
+        readings.extend(self.ibeacon.iter().flat_map(IBeacon::readings));
+        readings.extend(
+            self.eddystone
+                .iter()
+                .filter_map(Eddystone::telemetry)
+                .flat_map(|telemetry| telemetry.readings()),
+        );
+        readings.extend(self.ruuvi.iter().flat_map(Ruuvi::readings));
+        let bthome = self.bthome.iter().flat_map(|bthome| &bthome.readings);
+        let xiaomi = self.xiaomi.iter().flat_map(|xiaomi| &xiaomi.readings);
+        let govee = self.govee.iter().flat_map(|govee| &govee.readings);
+        let switchbot = self
+            .switchbot
+            .iter()
+            .flat_map(|switchbot| &switchbot.readings);
+        let victron = self.victron.iter().flat_map(|victron| &victron.readings);
+        let apple = self.apple.iter().flat_map(|apple| &apple.readings);
+        readings.extend(
+            bthome
+                .chain(xiaomi)
+                .chain(govee)
+                .chain(switchbot)
+                .chain(victron)
+                .chain(apple)
+                .map(Reading::from),
+        );
+        readings
+    }
+}
+
+impl From<&Device> for Decoded {
+    fn from(device: &Device) -> Self {
+        // devices rarely send data of several companies, pick one consistently if they do
+        let manufacturer_id = device.manufacturer_data.keys().min().copied();
+        let advertising = Advertising::from(device);
+        let appearance_name = advertising.appearance.and_then(assigned::appearance).map(
+            |appearance| match appearance {
+                (category, Some(subcategory)) => format!("{}: {}", category, subcategory),
+                (category, None) => category.to_owned(),
+            },
+        );
+        // advertisements list services in order of importance, BlueZ keeps them unordered
+        // and the advertising falls back to them sorted
+        let primary_service = [
+            &advertising.service_uuids_16,
+            &advertising.service_uuids_32,
+            &advertising.service_uuids_128,
+        ]
+        .into_iter()
+        .flatten()
+        .next()
+        .or_else(|| device.service_data.keys().min())
+        .copied();
+        let service_names = device
+            .uuids
+            .iter()
+            .chain(device.service_data.keys())
+            .chain(advertising.service_uuids_16.iter())
+            .chain(advertising.service_uuids_32.iter())
+            .chain(advertising.service_uuids_128.iter())
+            .filter_map(|uuid| Some((*uuid, assigned::service(*uuid)?)))
+            .collect();
+        let address_kind = AddressKind::from(device);
+        let mut decoded = Self {
+            address_kind,
+            identity: identity::resolve(device.address, address_kind),
+            manufacturer_id,
+            manufacturer_name: manufacturer_id.and_then(assigned::company),
+            appearance_name,
+            service_names,
+            primary_service,
+            class: device.class.map(DeviceClass::from),
+            advertising,
+            ibeacon: device
+                .manufacturer_data
+                .get(&ibeacon::COMPANY_ID)
+                .and_then(|data| IBeacon::parse(data)),
+            eddystone: device
+                .service_data
+                .get(&eddystone::service_uuid())
+                .and_then(|data| Eddystone::parse(data)),
+            ruuvi: device
+                .manufacturer_data
+                .get(&ruuvi::COMPANY_ID)
+                .and_then(|data| Ruuvi::parse(data)),
+            bthome: device
+                .service_data
+                .get(&bthome::service_uuid())
+                .and_then(|data| {
+                    let key = config::CONFIG.bthome.keys.get(&device.address);
+                    BtHome::parse(data, device.address, key)
+                }),
+            xiaomi: device
+                .service_data
+                .get(&xiaomi::custom_service_uuid())
+                .and_then(|data| Xiaomi::parse_custom(data))
+                .or_else(|| {
+                    let data = device.service_data.get(&xiaomi::mibeacon_service_uuid())?;
+                    let key = config::CONFIG.xiaomi.bindkeys.get(&device.address);
+                    Xiaomi::parse_mibeacon(data, device.address, key)
+                }),
+            govee: device
+                .manufacturer_data
+                .get(&govee::COMPANY_ID)
+                .and_then(|data| Govee::parse(data))
+                .or_else(|| {
+                    let data = device.manufacturer_data.get(&govee::H5179_COMPANY_ID)?;
+                    Govee::parse_h5179(data, device.name.as_deref()?)
+                }),
+            switchbot: device
+                .service_data
+                .get(&switchbot::service_uuid())
+                .and_then(|data| {
+                    let manufacturer_data = device.manufacturer_data.get(&switchbot::COMPANY_ID);
+                    SwitchBot::parse(data, manufacturer_data.map(Vec::as_slice))
+                }),
+            victron: device
+                .manufacturer_data
+                .get(&victron::COMPANY_ID)
+                .and_then(|data| {
+                    let key = config::CONFIG.victron.keys.get(&device.address);
+                    Victron::parse(data, device.address, key)
+                }),
+            apple: device
+                .manufacturer_data
+                .get(&apple::COMPANY_ID)
+                .and_then(|data| Apple::parse(data)),
+            microsoft: device
+                .manufacturer_data
+                .get(&microsoft::COMPANY_ID)
+                .and_then(|data| Microsoft::parse(data)),
+            fast_pair: device
+                .service_data
+                .get(&fast_pair::service_uuid())
+                .and_then(|data| FastPair::parse(data)),
+            exposure_notification: device
+                .service_data
+                .get(&exposure_notification::service_uuid())
+                .and_then(|data| ExposureNotification::parse(data)),
+            device_family: None,
+        };
+        decoded.device_family = decoded.family(device);
+        decoded
+    }
+}
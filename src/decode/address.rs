@@ -0,0 +1,55 @@
+use bluer::AddressType;
+use serde::Serialize;
+
+use crate::bluetooth::Device;
+use crate::config::{self, NonResolvable};
+
+/// Kind of a device address, LE random addresses are told apart by their two most significant bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressKind {
+    #[default]
+    Public,
+    Static,
+    Resolvable,
+    NonResolvable,
+    Reserved,
+}
+
+impl AddressKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Public => "public",
+            Self::Static => "static",
+            Self::Resolvable => "resolvable",
+            Self::NonResolvable => "non_resolvable",
+            Self::Reserved => "reserved",
+        }
+    }
+}
+
+impl From<&Device> for AddressKind {
+    fn from(device: &Device) -> Self {
+        if device.address_type != Some(AddressType::LeRandom) {
+            return Self::Public;
+        }
+        match device.address.0[0] >> 6 {
+            0b11 => Self::Static,
+            0b01 => Self::Resolvable,
+            0b00 => Self::NonResolvable,
+            _ => Self::Reserved,
+        }
+    }
+}
+
+/// Whether the device is only counted instead of getting series of its own.
+pub fn aggregated(device: &Device) -> bool {
+    config::CONFIG.bluetooth.non_resolvable == NonResolvable::Aggregate
+        && AddressKind::from(device) == AddressKind::NonResolvable
+}
+
+/// Whether events of the device are dropped altogether.
+pub fn dropped(device: &Device) -> bool {
+    config::CONFIG.bluetooth.non_resolvable == NonResolvable::Drop
+        && AddressKind::from(device) == AddressKind::NonResolvable
+}
@@ -0,0 +1,294 @@
+use bluer::{Uuid, UuidExt};
+use serde::Serialize;
+
+use crate::bluetooth::Device;
+
+const FLAGS: u8 = 0x01;
+const INCOMPLETE_UUIDS_16: u8 = 0x02;
+const COMPLETE_UUIDS_16: u8 = 0x03;
+const INCOMPLETE_UUIDS_32: u8 = 0x04;
+const COMPLETE_UUIDS_32: u8 = 0x05;
+const INCOMPLETE_UUIDS_128: u8 = 0x06;
+const COMPLETE_UUIDS_128: u8 = 0x07;
+const SHORT_LOCAL_NAME: u8 = 0x08;
+const COMPLETE_LOCAL_NAME: u8 = 0x09;
+const TX_POWER_LEVEL: u8 = 0x0a;
+const PERIPHERAL_INTERVAL_RANGE: u8 = 0x12;
+const APPEARANCE: u8 = 0x19;
+const LE_ROLE: u8 = 0x1c;
+const URI: u8 = 0x24;
+
+/// Connection intervals are expressed in units of 1.25ms.
+const INTERVAL_UNIT_MS: f64 = 1.25;
+/// Interval value meaning there is no specific minimum or maximum.
+const INTERVAL_UNSPECIFIED: u16 = 0xffff;
+
+/// URI scheme name string mapping from the Bluetooth assigned numbers.
+const URI_SCHEMES: &[(u8, &str)] = &[
+    (0x01, ""),
+    (0x02, "aaa:"),
+    (0x03, "aaas:"),
+    (0x04, "about:"),
+    (0x05, "acap:"),
+    (0x06, "acct:"),
+    (0x07, "cap:"),
+    (0x08, "cid:"),
+    (0x09, "coap:"),
+    (0x0a, "coaps:"),
+    (0x0b, "crid:"),
+    (0x0c, "data:"),
+    (0x0d, "dav:"),
+    (0x0e, "dict:"),
+    (0x0f, "dns:"),
+    (0x10, "file:"),
+    (0x11, "ftp:"),
+    (0x12, "geo:"),
+    (0x13, "go:"),
+    (0x14, "gopher:"),
+    (0x15, "h323:"),
+    (0x16, "http:"),
+    (0x17, "https:"),
+];
+
+/// The common AD structures of a device's advertisements, falling back to the
+/// device properties BlueZ parses some of them into.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Advertising {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<Flags>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub service_uuids_16: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub service_uuids_32: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub service_uuids_128: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appearance: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub le_role: Option<LeRole>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_power: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peripheral_interval_range: Option<IntervalRange>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Flags {
+    pub le_limited_discoverable: bool,
+    pub le_general_discoverable: bool,
+    pub br_edr_not_supported: bool,
+    pub simultaneous_le_br_edr_controller: bool,
+    pub simultaneous_le_br_edr_host: bool,
+}
+
+impl From<u8> for Flags {
+    fn from(flags: u8) -> Self {
+        Self {
+            le_limited_discoverable: flags & 0x01 != 0,
+            le_general_discoverable: flags & 0x02 != 0,
+            br_edr_not_supported: flags & 0x04 != 0,
+            simultaneous_le_br_edr_controller: flags & 0x08 != 0,
+            simultaneous_le_br_edr_host: flags & 0x10 != 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeRole {
+    PeripheralOnly,
+    CentralOnly,
+    PeripheralPreferred,
+    CentralPreferred,
+}
+
+impl LeRole {
+    fn from_u8(role: u8) -> Option<Self> {
+        match role {
+            0x00 => Some(Self::PeripheralOnly),
+            0x01 => Some(Self::CentralOnly),
+            0x02 => Some(Self::PeripheralPreferred),
+            0x03 => Some(Self::CentralPreferred),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::PeripheralOnly => "peripheral_only",
+            Self::CentralOnly => "central_only",
+            Self::PeripheralPreferred => "peripheral_preferred",
+            Self::CentralPreferred => "central_preferred",
+        }
+    }
+}
+
+/// Preferred connection interval range in milliseconds, `None` when unspecified.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IntervalRange {
+    pub min_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+impl Advertising {
+    /// Low-cardinality labels derived from the advertisement.
+    pub fn labels(&self) -> Vec<(&'static str, String)> {
+        let mut labels = vec![];
+        if let Some(flags) = self.flags {
+            let discoverable = if flags.le_limited_discoverable {
+                "limited"
+            } else if flags.le_general_discoverable {
+                "general"
+            } else {
+                "none"
+            };
+            labels.push(("discoverable", discoverable.to_owned()));
+        }
+        if let Some(le_role) = self.le_role {
+            labels.push(("le_role", le_role.as_str().to_owned()));
+        }
+        labels
+    }
+
+    fn apply(&mut self, ad_type: u8, value: &[u8]) {
+        match ad_type {
+            FLAGS if !value.is_empty() => self.flags = Some(Flags::from(value[0])),
+            INCOMPLETE_UUIDS_16 | COMPLETE_UUIDS_16 => self.service_uuids_16.extend(
+                value
+                    .chunks_exact(2)
+                    .map(|uuid| Uuid::from_u16(u16::from_le_bytes([uuid[0], uuid[1]]))),
+            ),
+            INCOMPLETE_UUIDS_32 | COMPLETE_UUIDS_32 => self.service_uuids_32.extend(
+                value
+                    .chunks_exact(4)
+                    .map(|uuid| Uuid::from_u32(u32::from_le_bytes(uuid.try_into().unwrap()))),
+            ),
+            INCOMPLETE_UUIDS_128 | COMPLETE_UUIDS_128 => {
+                self.service_uuids_128
+                    .extend(value.chunks_exact(16).map(|uuid| {
+                        let mut bytes: [u8; 16] = uuid.try_into().unwrap();
+                        bytes.reverse();
+                        Uuid::from_bytes(bytes)
+                    }))
+            }
+            SHORT_LOCAL_NAME => self.short_name = Some(String::from_utf8_lossy(value).into_owned()),
+            COMPLETE_LOCAL_NAME => {
+                self.local_name = Some(String::from_utf8_lossy(value).into_owned())
+            }
+            TX_POWER_LEVEL if value.len() == 1 => self.tx_power = Some(value[0] as i8),
+            PERIPHERAL_INTERVAL_RANGE if value.len() == 4 => {
+                let interval = |value: u16| {
+                    (value != INTERVAL_UNSPECIFIED).then_some(value as f64 * INTERVAL_UNIT_MS)
+                };
+                self.peripheral_interval_range = Some(IntervalRange {
+                    min_ms: interval(u16::from_le_bytes([value[0], value[1]])),
+                    max_ms: interval(u16::from_le_bytes([value[2], value[3]])),
+                });
+            }
+            APPEARANCE if value.len() == 2 => {
+                self.appearance = Some(u16::from_le_bytes([value[0], value[1]]))
+            }
+            LE_ROLE if value.len() == 1 => self.le_role = LeRole::from_u8(value[0]),
+            URI if !value.is_empty() => {
+                let rest = String::from_utf8_lossy(&value[1..]);
+                self.uri = Some(
+                    match URI_SCHEMES.iter().find(|(code, _)| *code == value[0]) {
+                        Some((_, scheme)) => format!("{}{}", scheme, rest),
+                        None => rest.into_owned(),
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+impl From<&Device> for Advertising {
+    fn from(device: &Device) -> Self {
+        let mut advertising = Advertising::default();
+        let mut ad_types: Vec<_> = device.advertising_data.keys().copied().collect();
+        ad_types.sort_unstable();
+        for ad_type in ad_types {
+            advertising.apply(ad_type, &device.advertising_data[&ad_type]);
+        }
+
+        // BlueZ parses these AD types itself and only exposes them as properties
+        if advertising.flags.is_none() {
+            advertising.flags = device.advertising_flags.first().copied().map(Flags::from);
+        }
+        if advertising.tx_power.is_none() {
+            advertising.tx_power = device.tx_power.map(|tx_power| tx_power as i8);
+        }
+        if advertising.appearance.is_none() {
+            advertising.appearance = device.appearance;
+        }
+        // the name may also be a short name, which BlueZ uses without a complete one
+        if advertising.local_name.is_none() && advertising.short_name.is_none() {
+            advertising.local_name = device.name.clone();
+        }
+        let uuids = [
+            &advertising.service_uuids_16,
+            &advertising.service_uuids_32,
+            &advertising.service_uuids_128,
+        ];
+        if uuids.iter().all(|uuids| uuids.is_empty()) {
+            let mut uuids: Vec<_> = device.uuids.iter().copied().collect();
+            uuids.sort_unstable();
+            for uuid in uuids {
+                if uuid.as_u16().is_some() {
+                    advertising.service_uuids_16.push(uuid);
+                } else if uuid.as_u32().is_some() {
+                    advertising.service_uuids_32.push(uuid);
+                } else {
+                    advertising.service_uuids_128.push(uuid);
+                }
+            }
+        }
+        advertising
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_bluez_properties() {
+        let custom = Uuid::parse_str("6e400001-b5a3-f393-e0a9-e50e24dcca9e").unwrap();
+        let device = Device {
+            name: Some("Sensor".to_owned()),
+            uuids: [Uuid::from_u16(0x181a), Uuid::from_u32(0x12345678), custom].into(),
+            ..Default::default()
+        };
+        let advertising = Advertising::from(&device);
+        assert_eq!(advertising.local_name.as_deref(), Some("Sensor"));
+        assert_eq!(advertising.service_uuids_16, [Uuid::from_u16(0x181a)]);
+        assert_eq!(advertising.service_uuids_32, [Uuid::from_u32(0x12345678)]);
+        assert_eq!(advertising.service_uuids_128, [custom]);
+    }
+
+    #[test]
+    fn prefers_advertised_structures() {
+        let device = Device {
+            name: Some("Sens".to_owned()),
+            uuids: [Uuid::from_u16(0x181a), Uuid::from_u16(0x180f)].into(),
+            advertising_data: [
+                (SHORT_LOCAL_NAME, b"Sens".to_vec()),
+                (COMPLETE_UUIDS_16, vec![0x0f, 0x18, 0x1a, 0x18]),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let advertising = Advertising::from(&device);
+        assert_eq!(advertising.local_name, None);
+        assert_eq!(advertising.short_name.as_deref(), Some("Sens"));
+        let uuids = [Uuid::from_u16(0x180f), Uuid::from_u16(0x181a)];
+        assert_eq!(advertising.service_uuids_16, uuids);
+    }
+}
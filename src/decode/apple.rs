@@ -0,0 +1,179 @@
+use serde::Serialize;
+
+use super::SensorReading;
+
+pub use super::ibeacon::COMPANY_ID;
+
+const PROXIMITY_PAIRING: u8 = 0x07;
+const PROXIMITY_PAIRING_LEN: usize = 25;
+/// Set in the status when the left pod's values are in the low bits.
+const LEFT_LOW: u8 = 0x20;
+/// Battery levels are in tens of percent, this one means unknown.
+const BATTERY_UNKNOWN: u8 = 0x0f;
+/// iBeacons use the Continuity format, but are sent by beacons of many manufacturers.
+const IBEACON: &str = "ibeacon";
+
+/// Continuity message types, see <https://github.com/furiousMAC/continuity>.
+const MESSAGE_TYPES: &[(u8, &str)] = &[
+    (0x02, IBEACON),
+    (0x03, "airprint"),
+    (0x05, "airdrop"),
+    (0x06, "homekit"),
+    (PROXIMITY_PAIRING, "proximity_pairing"),
+    (0x08, "hey_siri"),
+    (0x09, "airplay_target"),
+    (0x0a, "airplay_source"),
+    (0x0b, "magic_switch"),
+    (0x0c, "handoff"),
+    (0x0d, "tethering_target"),
+    (0x0e, "tethering_source"),
+    (0x0f, "nearby_action"),
+    (0x10, "nearby_info"),
+    (0x12, "find_my"),
+];
+
+/// Models sending proximity pairing messages.
+const MODELS: &[(u16, &str)] = &[
+    (0x0220, "AirPods"),
+    (0x0320, "Powerbeats3"),
+    (0x0520, "BeatsX"),
+    (0x0620, "Beats Solo3"),
+    (0x0920, "Beats Studio3"),
+    (0x0a20, "AirPods Max"),
+    (0x0b20, "Powerbeats Pro"),
+    (0x0e20, "AirPods Pro"),
+    (0x0f20, "AirPods (2nd generation)"),
+    (0x1020, "Beats Flex"),
+    (0x1120, "Beats Studio Buds"),
+    (0x1320, "AirPods (3rd generation)"),
+    (0x1420, "AirPods Pro (2nd generation)"),
+];
+
+/// Apple Continuity messages of a device.
+#[derive(Debug, Clone, Serialize)]
+pub struct Apple {
+    /// Types of the messages, in hex for types without a name.
+    pub messages: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub airpods: Option<AirPods>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub readings: Vec<SensorReading>,
+}
+
+/// Headphones announcing themselves to the paired iCloud account.
+#[derive(Debug, Clone, Serialize)]
+pub struct AirPods {
+    pub model_id: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<&'static str>,
+}
+
+impl Apple {
+    /// Parse the type-length-value messages of the manufacturer data of company [`COMPANY_ID`].
+    pub fn parse(mut data: &[u8]) -> Option<Self> {
+        let mut apple = Self {
+            messages: vec![],
+            airpods: None,
+            readings: vec![],
+        };
+        while data.len() >= 2 {
+            let (kind, len) = (data[0], data[1] as usize);
+            let value = match data.get(2..2 + len) {
+                Some(value) => value,
+                None => {
+                    // keep the messages before it, they are complete
+                    log::debug!("truncated Apple Continuity message {:#04x}", kind);
+                    break;
+                }
+            };
+            let name = match MESSAGE_TYPES.iter().find(|(id, _)| *id == kind) {
+                Some((_, name)) => name.to_string(),
+                None => format!("{:#04x}", kind),
+            };
+            apple.messages.push(name);
+            if kind == PROXIMITY_PAIRING && len == PROXIMITY_PAIRING_LEN {
+                apple.proximity_pairing(value);
+            }
+            data = &data[2 + len..];
+        }
+        if apple.messages.is_empty() {
+            return None;
+        }
+        Some(apple)
+    }
+
+    /// Whether the messages show an Apple device, rather than only an iBeacon.
+    pub fn is_apple(&self) -> bool {
+        self.messages.iter().any(|message| message != IBEACON)
+    }
+
+    fn proximity_pairing(&mut self, value: &[u8]) {
+        let model_id = u16::from_be_bytes([value[1], value[2]]);
+        self.airpods = Some(AirPods {
+            model_id,
+            model: MODELS
+                .iter()
+                .find(|(id, _)| *id == model_id)
+                .map(|(_, name)| *name),
+        });
+
+        let (status, pods, case) = (value[3], value[4], value[5]);
+        let (left, right) = if status & LEFT_LOW != 0 {
+            (pods & 0x0f, pods >> 4)
+        } else {
+            (pods >> 4, pods & 0x0f)
+        };
+        let charging = case >> 4;
+        let (left_charging, right_charging) = if status & LEFT_LOW != 0 {
+            (charging & 0x01, charging & 0x02)
+        } else {
+            (charging & 0x02, charging & 0x01)
+        };
+        for (battery_kind, charging_kind, battery, charging) in [
+            ("left_battery", "left_charging", left, left_charging),
+            ("right_battery", "right_charging", right, right_charging),
+            (
+                "case_battery",
+                "case_charging",
+                case & 0x0f,
+                charging & 0x04,
+            ),
+        ] {
+            if battery == BATTERY_UNKNOWN {
+                continue;
+            }
+            let level = (battery.min(10) * 10).into();
+            let charging = if charging != 0 { 1.0 } else { 0.0 };
+            self.readings.extend([
+                SensorReading::new(battery_kind, "percent", level),
+                SensorReading::new(charging_kind, "", charging),
+            ]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_unknown_types_in_hex() {
+        let apple = Apple::parse(&[0x10, 0x02, 0x1b, 0x1c, 0x13, 0x01, 0x00]).unwrap();
+        assert_eq!(apple.messages, ["nearby_info", "0x13"]);
+        assert!(apple.is_apple());
+    }
+
+    #[test]
+    fn keeps_messages_before_a_truncated_one() {
+        let apple = Apple::parse(&[0x10, 0x02, 0x1b, 0x1c, 0x0c, 0x0e, 0x00]).unwrap();
+        assert_eq!(apple.messages, ["nearby_info"]);
+        assert!(Apple::parse(&[0x0c, 0x0e, 0x00]).is_none());
+    }
+
+    #[test]
+    fn tells_ibeacons_apart() {
+        let mut data = vec![0x02, 0x15];
+        data.extend([0; 21]);
+        assert!(!Apple::parse(&data).unwrap().is_apple());
+    }
+}
@@ -0,0 +1,195 @@
+//! Bluetooth SIG assigned numbers.
+
+use bluer::id::{Manufacturer, Service, ServiceClass};
+use bluer::{Uuid, UuidExt};
+
+/// 16-bit UUIDs of SIG members that bluer does not know about.
+const MEMBER_SERVICES: &[(u16, &str)] = &[(0xfcd2, "BTHome"), (0xfe95, "Xiaomi MiBeacon")];
+
+/// An appearance category and its subcategories, subcategory `0` is the generic one.
+type Category = (u16, &'static str, &'static [(u8, &'static str)]);
+
+#[rustfmt::skip]
+const APPEARANCES: &[Category] = &[
+    (0x000, "Unknown", &[]),
+    (0x001, "Phone", &[]),
+    (0x002, "Computer", &[
+        (0x01, "Desktop Workstation"), (0x02, "Server-class Computer"), (0x03, "Laptop"),
+        (0x04, "Handheld PC/PDA (clamshell)"), (0x05, "Palm-size PC/PDA"),
+        (0x06, "Wearable computer (watch size)"), (0x07, "Tablet"), (0x08, "Docking Station"),
+        (0x09, "All in One"), (0x0a, "Blade Server"), (0x0b, "Convertible"), (0x0c, "Detachable"),
+        (0x0d, "IoT Gateway"), (0x0e, "Mini PC"), (0x0f, "Stick PC"),
+    ]),
+    (0x003, "Watch", &[(0x01, "Sports Watch"), (0x02, "Smartwatch")]),
+    (0x004, "Clock", &[]),
+    (0x005, "Display", &[]),
+    (0x006, "Remote Control", &[]),
+    (0x007, "Eye-glasses", &[]),
+    (0x008, "Tag", &[]),
+    (0x009, "Keyring", &[]),
+    (0x00a, "Media Player", &[]),
+    (0x00b, "Barcode Scanner", &[]),
+    (0x00c, "Thermometer", &[(0x01, "Ear Thermometer")]),
+    (0x00d, "Heart Rate Sensor", &[(0x01, "Heart Rate Belt")]),
+    (0x00e, "Blood Pressure", &[(0x01, "Arm Blood Pressure"), (0x02, "Wrist Blood Pressure")]),
+    (0x00f, "Human Interface Device", &[
+        (0x01, "Keyboard"), (0x02, "Mouse"), (0x03, "Joystick"), (0x04, "Gamepad"),
+        (0x05, "Digitizer Tablet"), (0x06, "Card Reader"), (0x07, "Digital Pen"),
+        (0x08, "Barcode Scanner"), (0x09, "Touchpad"), (0x0a, "Presentation Remote"),
+    ]),
+    (0x010, "Glucose Meter", &[]),
+    (0x011, "Running Walking Sensor", &[(0x01, "In-Shoe"), (0x02, "On-Shoe"), (0x03, "On-Hip")]),
+    (0x012, "Cycling", &[
+        (0x01, "Cycling Computer"), (0x02, "Speed Sensor"), (0x03, "Cadence Sensor"),
+        (0x04, "Power Sensor"), (0x05, "Speed and Cadence Sensor"),
+    ]),
+    (0x013, "Control Device", &[
+        (0x01, "Switch"), (0x02, "Multi-switch"), (0x03, "Button"), (0x04, "Slider"),
+        (0x05, "Rotary Switch"), (0x06, "Touch Panel"), (0x07, "Single Switch"),
+        (0x08, "Double Switch"), (0x09, "Triple Switch"), (0x0a, "Battery Switch"),
+        (0x0b, "Energy Harvesting Switch"), (0x0c, "Push Button"), (0x0d, "Dial"),
+    ]),
+    (0x014, "Network Device", &[(0x01, "Access Point"), (0x02, "Mesh Device"), (0x03, "Mesh Network Proxy")]),
+    (0x015, "Sensor", &[
+        (0x01, "Motion Sensor"), (0x02, "Air quality Sensor"), (0x03, "Temperature Sensor"),
+        (0x04, "Humidity Sensor"), (0x05, "Leak Sensor"), (0x06, "Smoke Sensor"),
+        (0x07, "Occupancy Sensor"), (0x08, "Contact Sensor"), (0x09, "Carbon Monoxide Sensor"),
+        (0x0a, "Carbon Dioxide Sensor"), (0x0b, "Ambient Light Sensor"), (0x0c, "Energy Sensor"),
+        (0x0d, "Color Light Sensor"), (0x0e, "Rain Sensor"), (0x0f, "Fire Sensor"),
+        (0x10, "Wind Sensor"), (0x11, "Proximity Sensor"), (0x12, "Multi-Sensor"),
+        (0x13, "Flush Mounted Sensor"), (0x14, "Ceiling Mounted Sensor"),
+        (0x15, "Wall Mounted Sensor"), (0x16, "Multisensor"), (0x17, "Energy Meter"),
+        (0x18, "Flame Detector"), (0x19, "Vehicle Tire Pressure Sensor"),
+    ]),
+    (0x016, "Light Fixtures", &[
+        (0x01, "Wall Light"), (0x02, "Ceiling Light"), (0x03, "Floor Light"),
+        (0x04, "Cabinet Light"), (0x05, "Desk Light"), (0x06, "Troffer Light"),
+        (0x07, "Pendant Light"), (0x08, "In-ground Light"), (0x09, "Flood Light"),
+        (0x0a, "Underwater Light"), (0x0b, "Bollard with Light"), (0x0c, "Pathway Light"),
+        (0x0d, "Garden Light"), (0x0e, "Pole-top Light"), (0x0f, "Spotlight"),
+        (0x10, "Linear Light"), (0x11, "Street Light"), (0x12, "Shelves Light"),
+        (0x13, "Bay Light"), (0x14, "Emergency Exit Light"), (0x15, "Light Controller"),
+        (0x16, "Light Driver"), (0x17, "Bulb"), (0x18, "Low-bay Light"), (0x19, "High-bay Light"),
+    ]),
+    (0x017, "Fan", &[
+        (0x01, "Ceiling Fan"), (0x02, "Axial Fan"), (0x03, "Exhaust Fan"),
+        (0x04, "Pedestal Fan"), (0x05, "Desk Fan"), (0x06, "Wall Fan"),
+    ]),
+    (0x018, "HVAC", &[
+        (0x01, "Thermostat"), (0x02, "Humidifier"), (0x03, "De-humidifier"), (0x04, "Heater"),
+        (0x05, "Radiator"), (0x06, "Boiler"), (0x07, "Heat Pump"), (0x08, "Infrared Heater"),
+        (0x09, "Radiant Panel Heater"), (0x0a, "Fan Heater"), (0x0b, "Air Curtain"),
+    ]),
+    (0x019, "Air Conditioning", &[]),
+    (0x01a, "Humidifier", &[]),
+    (0x01b, "Heating", &[
+        (0x01, "Radiator"), (0x02, "Boiler"), (0x03, "Heat Pump"), (0x04, "Infrared Heater"),
+        (0x05, "Radiant Panel Heater"), (0x06, "Fan Heater"), (0x07, "Air Curtain"),
+    ]),
+    (0x01c, "Access Control", &[
+        (0x01, "Access Door"), (0x02, "Garage Door"), (0x03, "Emergency Exit Door"),
+        (0x04, "Access Lock"), (0x05, "Elevator"), (0x06, "Window"), (0x07, "Entrance Gate"),
+        (0x08, "Door Lock"), (0x09, "Locker"),
+    ]),
+    (0x01d, "Motorized Device", &[
+        (0x01, "Motorized Gate"), (0x02, "Awning"), (0x03, "Blinds or Shades"),
+        (0x04, "Curtains"), (0x05, "Screen"),
+    ]),
+    (0x01e, "Power Device", &[
+        (0x01, "Power Outlet"), (0x02, "Power Strip"), (0x03, "Plug"), (0x04, "Power Supply"),
+        (0x05, "LED Driver"), (0x06, "Fluorescent Lamp Gear"), (0x07, "HID Lamp Gear"),
+        (0x08, "Charge Case"), (0x09, "Power Bank"),
+    ]),
+    (0x01f, "Light Source", &[
+        (0x01, "Incandescent Light Bulb"), (0x02, "LED Lamp"), (0x03, "HID Lamp"),
+        (0x04, "Fluorescent Lamp"), (0x05, "LED Array"), (0x06, "Multi-Color LED Array"),
+        (0x07, "Low voltage halogen"), (0x08, "Organic light emitting diode (OLED)"),
+    ]),
+    (0x020, "Window Covering", &[
+        (0x01, "Window Shades"), (0x02, "Window Blinds"), (0x03, "Window Awning"),
+        (0x04, "Window Curtain"), (0x05, "Exterior Shutter"), (0x06, "Exterior Screen"),
+    ]),
+    (0x021, "Audio Sink", &[
+        (0x01, "Standalone Speaker"), (0x02, "Soundbar"), (0x03, "Bookshelf Speaker"),
+        (0x04, "Standmounted Speaker"), (0x05, "Speakerphone"),
+    ]),
+    (0x022, "Audio Source", &[
+        (0x01, "Microphone"), (0x02, "Alarm"), (0x03, "Bell"), (0x04, "Horn"),
+        (0x05, "Broadcasting Device"), (0x06, "Service Desk"), (0x07, "Kiosk"),
+        (0x08, "Broadcasting Room"), (0x09, "Auditorium"),
+    ]),
+    (0x023, "Motorized Vehicle", &[
+        (0x01, "Car"), (0x02, "Large Goods Vehicle"), (0x03, "2-Wheeled Vehicle"),
+        (0x04, "Motorbike"), (0x05, "Scooter"), (0x06, "Moped"), (0x07, "3-Wheeled Vehicle"),
+        (0x08, "Light Vehicle"), (0x09, "Quad Bike"), (0x0a, "Minibus"), (0x0b, "Bus"),
+        (0x0c, "Trolley"), (0x0d, "Agricultural Vehicle"), (0x0e, "Camper / Caravan"),
+        (0x0f, "Recreational Vehicle / Motor Home"),
+    ]),
+    (0x024, "Domestic Appliance", &[
+        (0x01, "Refrigerator"), (0x02, "Freezer"), (0x03, "Oven"), (0x04, "Microwave"),
+        (0x05, "Toaster"), (0x06, "Washing Machine"), (0x07, "Dryer"), (0x08, "Coffee maker"),
+        (0x09, "Clothes iron"), (0x0a, "Curling iron"), (0x0b, "Hair dryer"),
+        (0x0c, "Vacuum cleaner"), (0x0d, "Robotic vacuum cleaner"), (0x0e, "Rice cooker"),
+        (0x0f, "Clothes steamer"),
+    ]),
+    (0x025, "Wearable Audio Device", &[
+        (0x01, "Earbud"), (0x02, "Headset"), (0x03, "Headphones"), (0x04, "Neck Band"),
+    ]),
+    (0x026, "Aircraft", &[
+        (0x01, "Light Aircraft"), (0x02, "Microlight"), (0x03, "Paraglider"),
+        (0x04, "Large Passenger Aircraft"),
+    ]),
+    (0x027, "AV Equipment", &[]),
+    (0x028, "Display Equipment", &[(0x01, "Television"), (0x02, "Monitor"), (0x03, "Projector")]),
+    (0x029, "Hearing aid", &[
+        (0x01, "In-ear hearing aid"), (0x02, "Behind-ear hearing aid"), (0x03, "Cochlear Implant"),
+    ]),
+    (0x02a, "Gaming", &[(0x01, "Home Video Game Console"), (0x02, "Portable handheld console")]),
+    (0x02b, "Signage", &[(0x01, "Digital Signage"), (0x02, "Electronic Label")]),
+    (0x031, "Pulse Oximeter", &[(0x01, "Fingertip Pulse Oximeter"), (0x02, "Wrist Worn Pulse Oximeter")]),
+    (0x032, "Weight Scale", &[]),
+    (0x033, "Personal Mobility Device", &[(0x01, "Powered Wheelchair"), (0x02, "Mobility Scooter")]),
+    (0x034, "Continuous Glucose Monitor", &[]),
+    (0x035, "Insulin Pump", &[
+        (0x01, "Insulin Pump, durable pump"), (0x04, "Insulin Pump, patch pump"), (0x08, "Insulin Pen"),
+    ]),
+    (0x036, "Medication Delivery", &[]),
+    (0x037, "Spirometer", &[(0x01, "Handheld Spirometer")]),
+    (0x051, "Outdoor Sports Activity", &[
+        (0x01, "Location Display"), (0x02, "Location and Navigation Display"),
+        (0x03, "Location Pod"), (0x04, "Location and Navigation Pod"),
+    ]),
+];
+
+/// Name of the company with the given company identifier, from the table bundled with bluer.
+pub fn company(id: u16) -> Option<String> {
+    Manufacturer::try_from(id)
+        .ok()
+        .map(|company| company.to_string())
+}
+
+/// Name of a GATT service, service class or profile.
+pub fn service(uuid: Uuid) -> Option<String> {
+    if let Ok(service) = Service::try_from(uuid) {
+        return Some(service.to_string());
+    }
+    if let Ok(class) = ServiceClass::try_from(uuid) {
+        return Some(class.to_string());
+    }
+    let short = uuid.as_u16()?;
+    MEMBER_SERVICES
+        .iter()
+        .find(|(id, _)| *id == short)
+        .map(|(_, name)| name.to_string())
+}
+
+/// Category and, unless it is the generic one, subcategory name of an appearance value.
+pub fn appearance(appearance: u16) -> Option<(&'static str, Option<&'static str>)> {
+    let (category, subcategory) = (appearance >> 6, (appearance & 0x3f) as u8);
+    let (_, name, subcategories) = APPEARANCES.iter().find(|(id, _, _)| *id == category)?;
+    let subcategory = subcategories
+        .iter()
+        .find(|(id, _)| *id == subcategory)
+        .map(|(_, name)| *name);
+    Some((name, subcategory))
+}
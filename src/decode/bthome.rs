@@ -0,0 +1,379 @@
+use bluer::{Address, Uuid, UuidExt};
+use serde::Serialize;
+
+use crate::config::Key;
+
+use super::crypto;
+use super::Reading;
+
+/// Service data UUID of BTHome advertisements.
+pub const SERVICE_UUID: u16 = 0xfcd2;
+
+const VERSION: u8 = 2;
+const FLAG_ENCRYPTED: u8 = 0x01;
+const FLAG_TRIGGER_BASED: u8 = 0x04;
+
+const PACKET_ID: u8 = 0x00;
+const DIMMER: u8 = 0x3c;
+const TEXT: u8 = 0x53;
+const RAW: u8 = 0x54;
+const DEVICE_TYPE: u8 = 0xf0;
+const FIRMWARE_VERSION_32: u8 = 0xf1;
+const FIRMWARE_VERSION_24: u8 = 0xf2;
+
+/// A numeric object: id, type, unit, size in bytes, signedness and factor.
+type Object = (u8, &'static str, &'static str, usize, bool, f64);
+
+/// Numeric objects of <https://bthome.io/format/>, ordered by id.
+const OBJECTS: &[Object] = &[
+    (0x01, "battery", "percent", 1, false, 1.0),
+    (0x02, "temperature", "celsius", 2, true, 0.01),
+    (0x03, "humidity", "percent", 2, false, 0.01),
+    (0x04, "pressure", "hectopascals", 3, false, 0.01),
+    (0x05, "illuminance", "lux", 3, false, 0.01),
+    (0x06, "mass", "kilograms", 2, false, 0.01),
+    (0x07, "mass", "pounds", 2, false, 0.01),
+    (0x08, "dewpoint", "celsius", 2, true, 0.01),
+    (0x09, "count", "", 1, false, 1.0),
+    (0x0a, "energy", "kilowatt_hours", 3, false, 0.001),
+    (0x0b, "power", "watts", 3, false, 0.01),
+    (0x0c, "voltage", "volts", 2, false, 0.001),
+    (0x0d, "pm2_5", "micrograms_per_cubic_meter", 2, false, 1.0),
+    (0x0e, "pm10", "micrograms_per_cubic_meter", 2, false, 1.0),
+    (0x0f, "generic_boolean", "", 1, false, 1.0),
+    (0x10, "power_on", "", 1, false, 1.0),
+    (0x11, "opening", "", 1, false, 1.0),
+    (0x12, "co2", "ppm", 2, false, 1.0),
+    (0x13, "tvoc", "micrograms_per_cubic_meter", 2, false, 1.0),
+    (0x14, "moisture", "percent", 2, false, 0.01),
+    (0x15, "battery_low", "", 1, false, 1.0),
+    (0x16, "battery_charging", "", 1, false, 1.0),
+    (0x17, "carbon_monoxide", "", 1, false, 1.0),
+    (0x18, "cold", "", 1, false, 1.0),
+    (0x19, "connectivity", "", 1, false, 1.0),
+    (0x1a, "door", "", 1, false, 1.0),
+    (0x1b, "garage_door", "", 1, false, 1.0),
+    (0x1c, "gas_detected", "", 1, false, 1.0),
+    (0x1d, "heat", "", 1, false, 1.0),
+    (0x1e, "light", "", 1, false, 1.0),
+    (0x1f, "lock", "", 1, false, 1.0),
+    (0x20, "moisture_detected", "", 1, false, 1.0),
+    (0x21, "motion", "", 1, false, 1.0),
+    (0x22, "moving", "", 1, false, 1.0),
+    (0x23, "occupancy", "", 1, false, 1.0),
+    (0x24, "plug", "", 1, false, 1.0),
+    (0x25, "presence", "", 1, false, 1.0),
+    (0x26, "problem", "", 1, false, 1.0),
+    (0x27, "running", "", 1, false, 1.0),
+    (0x28, "safety", "", 1, false, 1.0),
+    (0x29, "smoke", "", 1, false, 1.0),
+    (0x2a, "sound", "", 1, false, 1.0),
+    (0x2b, "tamper", "", 1, false, 1.0),
+    (0x2c, "vibration", "", 1, false, 1.0),
+    (0x2d, "window", "", 1, false, 1.0),
+    (0x2e, "humidity", "percent", 1, false, 1.0),
+    (0x2f, "moisture", "percent", 1, false, 1.0),
+    (0x3a, "button", "", 1, false, 1.0),
+    (0x3d, "count", "", 2, false, 1.0),
+    (0x3e, "count", "", 4, false, 1.0),
+    (0x3f, "rotation", "degrees", 2, true, 0.1),
+    (0x40, "distance", "millimeters", 2, false, 1.0),
+    (0x41, "distance", "meters", 2, false, 0.1),
+    (0x42, "duration", "seconds", 3, false, 0.001),
+    (0x43, "current", "amperes", 2, false, 0.001),
+    (0x44, "speed", "meters_per_second", 2, false, 0.01),
+    (0x45, "temperature", "celsius", 2, true, 0.1),
+    (0x46, "uv_index", "", 1, false, 0.1),
+    (0x47, "volume", "liters", 2, false, 0.1),
+    (0x48, "volume", "milliliters", 2, false, 1.0),
+    (
+        0x49,
+        "volume_flow_rate",
+        "cubic_meters_per_hour",
+        2,
+        false,
+        0.001,
+    ),
+    (0x4a, "voltage", "volts", 2, false, 0.1),
+    (0x4b, "gas", "cubic_meters", 3, false, 0.001),
+    (0x4c, "gas", "cubic_meters", 4, false, 0.001),
+    (0x4d, "energy", "kilowatt_hours", 4, false, 0.001),
+    (0x4e, "volume", "liters", 4, false, 0.001),
+    (0x4f, "water", "liters", 4, false, 0.001),
+    (0x50, "timestamp", "seconds", 4, false, 1.0),
+    (
+        0x51,
+        "acceleration",
+        "meters_per_second_squared",
+        2,
+        false,
+        0.001,
+    ),
+    (0x52, "gyroscope", "degrees_per_second", 2, false, 0.001),
+    (0x55, "volume_storage", "liters", 4, false, 0.001),
+    (
+        0x56,
+        "conductivity",
+        "microsiemens_per_centimeter",
+        2,
+        false,
+        1.0,
+    ),
+    (0x57, "temperature", "celsius", 1, true, 1.0),
+    (0x58, "temperature", "celsius", 1, true, 0.35),
+    (0x59, "count", "", 1, true, 1.0),
+    (0x5a, "count", "", 2, true, 1.0),
+    (0x5b, "count", "", 4, true, 1.0),
+    (0x5c, "power", "watts", 4, true, 0.01),
+    (0x5d, "current", "amperes", 2, true, 0.001),
+    (0x5e, "direction", "degrees", 2, false, 0.01),
+    (0x5f, "precipitation", "millimeters", 2, false, 0.1),
+    (0x60, "channel", "", 1, false, 1.0),
+    (0x61, "rotational_speed", "rpm", 2, false, 1.0),
+];
+
+pub fn service_uuid() -> Uuid {
+    Uuid::from_u16(SERVICE_UUID)
+}
+
+/// A BTHome v2 advertisement.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BtHome {
+    pub encrypted: bool,
+    pub trigger_based: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packet_id: Option<u8>,
+    pub readings: Vec<SensorReading>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub text: Vec<String>,
+    /// Raw objects, hex encoded.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub raw: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_type: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SensorReading {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub unit: &'static str,
+    /// Position among the readings of the same type, devices may send several.
+    pub index: usize,
+    pub value: f64,
+}
+
+impl SensorReading {
+    /// The only reading of its type.
+    pub fn new(kind: &'static str, unit: &'static str, value: f64) -> Self {
+        Self {
+            kind,
+            unit,
+            index: 0,
+            value,
+        }
+    }
+}
+
+impl From<&SensorReading> for Reading {
+    fn from(reading: &SensorReading) -> Self {
+        // the unit differs between the series of the metric, so it is only a label
+        Reading::new(
+            "bluetooth_sensor_value",
+            "A sensor reading decoded from the advertisements of the bluetooth device.",
+            "",
+            reading.value,
+        )
+        .label("type", reading.kind)
+        .label("unit", reading.unit)
+        .label("index", reading.index.to_string())
+    }
+}
+
+impl BtHome {
+    /// Parse the service data of UUID [`SERVICE_UUID`], decrypting it with `key` if needed.
+    pub fn parse(data: &[u8], address: Address, key: Option<&Key>) -> Option<Self> {
+        let info = *data.first()?;
+        if info >> 5 != VERSION {
+            return None;
+        }
+        let encrypted = info & FLAG_ENCRYPTED != 0;
+        let payload = if encrypted {
+            decrypt(data, address, key?)?
+        } else {
+            data[1..].to_vec()
+        };
+
+        let mut bthome = Self {
+            encrypted,
+            trigger_based: info & FLAG_TRIGGER_BASED != 0,
+            ..Default::default()
+        };
+        let mut objects = payload.as_slice();
+        while let Some((&id, rest)) = objects.split_first() {
+            objects = match bthome.object(id, rest) {
+                Some(rest) => rest,
+                None => {
+                    // the size of an unknown object is unknown too, so nothing after it can be read
+                    log::debug!(
+                        "{}: unknown or truncated BTHome object {:#04x}",
+                        address,
+                        id
+                    );
+                    break;
+                }
+            };
+        }
+        Some(bthome)
+    }
+
+    /// Read the object `id` from the start of `data`, returning the data after it.
+    fn object<'a>(&mut self, id: u8, data: &'a [u8]) -> Option<&'a [u8]> {
+        let size = match id {
+            PACKET_ID => 1,
+            DIMMER => 2,
+            TEXT | RAW => 1 + *data.first()? as usize,
+            DEVICE_TYPE => 2,
+            FIRMWARE_VERSION_32 => 4,
+            FIRMWARE_VERSION_24 => 3,
+            _ => object(id)?.3,
+        };
+        if data.len() < size {
+            return None;
+        }
+        let (value, rest) = data.split_at(size);
+
+        match id {
+            PACKET_ID => self.packet_id = Some(value[0]),
+            DIMMER => {
+                // event 1 rotates left, 2 rotates right, by the given number of steps
+                let steps = value[1] as f64;
+                let steps = match value[0] {
+                    1 => -steps,
+                    2 => steps,
+                    _ => 0.0,
+                };
+                self.push("dimmer", "", steps);
+            }
+            TEXT => self
+                .text
+                .push(String::from_utf8_lossy(&value[1..]).into_owned()),
+            RAW => self
+                .raw
+                .push(value[1..].iter().map(|b| format!("{:02x}", b)).collect()),
+            DEVICE_TYPE => self.device_type = Some(u16::from_le_bytes([value[0], value[1]])),
+            FIRMWARE_VERSION_32 | FIRMWARE_VERSION_24 => {
+                let version: Vec<_> = value.iter().rev().map(|part| part.to_string()).collect();
+                self.firmware_version = Some(version.join("."));
+            }
+            _ => {
+                let (_, kind, unit, _, signed, factor) = object(id)?;
+                // dividing keeps decimal factors like 0.01 from adding rounding noise
+                self.push(kind, unit, integer(value, signed) as f64 / (1.0 / factor));
+            }
+        }
+        Some(rest)
+    }
+
+    fn push(&mut self, kind: &'static str, unit: &'static str, value: f64) {
+        let index = self
+            .readings
+            .iter()
+            .filter(|reading| reading.kind == kind)
+            .count();
+        self.readings.push(SensorReading {
+            kind,
+            unit,
+            index,
+            value,
+        });
+    }
+}
+
+fn object(id: u8) -> Option<Object> {
+    OBJECTS.iter().find(|object| object.0 == id).copied()
+}
+
+/// Little endian integer of up to 4 bytes.
+fn integer(bytes: &[u8], signed: bool) -> i64 {
+    let mut buf = [0; 8];
+    buf[..bytes.len()].copy_from_slice(bytes);
+    let value = i64::from_le_bytes(buf);
+    let bits = bytes.len() as u32 * 8;
+    if signed && value >> (bits - 1) != 0 {
+        value - (1 << bits)
+    } else {
+        value
+    }
+}
+
+/// Decrypt the objects of an encrypted advertisement, laid out as
+/// device info, ciphertext, 4 byte counter and 4 byte message integrity check.
+fn decrypt(data: &[u8], address: Address, key: &Key) -> Option<Vec<u8>> {
+    if data.len() < 9 {
+        return None;
+    }
+    let (ciphertext, trailer) = data[1..].split_at(data.len() - 9);
+    let (counter, mic) = trailer.split_at(4);
+
+    let mut nonce = address.0.to_vec();
+    nonce.extend_from_slice(&SERVICE_UUID.to_le_bytes());
+    nonce.push(data[0]);
+    nonce.extend_from_slice(counter);
+
+    match crypto::decrypt_ccm(key, &nonce, &[], ciphertext, mic) {
+        Ok(payload) => Some(payload),
+        Err(err) => {
+            log::debug!(
+                "{}: failed to decrypt BTHome advertisement: {}",
+                address,
+                err
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The encrypted example of <https://bthome.io/encryption/>.
+    const KEY: &str = "231d39c1d7cc1ab1aee224cd096db932";
+    const ADDRESS: Address = Address([0x54, 0x48, 0xe6, 0x8f, 0x80, 0xa5]);
+    const DATA: [u8; 15] = [
+        0x41, // encrypted BTHome v2
+        0xa4, 0x72, 0x66, 0xc9, 0x5f, 0x73, // ciphertext
+        0x00, 0x11, 0x22, 0x33, // counter
+        0x78, 0x23, 0x72, 0x14, // message integrity check
+    ];
+
+    #[test]
+    fn decrypts_example() {
+        let key: Key = KEY.parse().unwrap();
+        let bthome = BtHome::parse(&DATA, ADDRESS, Some(&key)).unwrap();
+        assert!(bthome.encrypted);
+        let readings: Vec<_> = bthome
+            .readings
+            .iter()
+            .map(|reading| (reading.kind, reading.unit, reading.value))
+            .collect();
+        assert_eq!(
+            readings,
+            [
+                ("temperature", "celsius", 25.06),
+                ("humidity", "percent", 50.55)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_key() {
+        let mut key: Key = KEY.parse().unwrap();
+        key.0[0] ^= 1;
+        assert!(BtHome::parse(&DATA, ADDRESS, Some(&key)).is_none());
+        assert!(BtHome::parse(&DATA, ADDRESS, None).is_none());
+    }
+}
@@ -0,0 +1,191 @@
+use serde::Serialize;
+
+/// Major service class bits, from bit 13 on.
+const SERVICE_CLASSES: &[(u32, &str)] = &[
+    (13, "Limited Discoverable Mode"),
+    (14, "LE audio"),
+    (16, "Positioning"),
+    (17, "Networking"),
+    (18, "Rendering"),
+    (19, "Capturing"),
+    (20, "Object Transfer"),
+    (21, "Audio"),
+    (22, "Telephony"),
+    (23, "Information"),
+];
+
+const COMPUTER: &[&str] = &[
+    "Uncategorized",
+    "Desktop workstation",
+    "Server-class computer",
+    "Laptop",
+    "Handheld PC/PDA (clamshell)",
+    "Palm-size PC/PDA",
+    "Wearable computer (watch size)",
+    "Tablet",
+];
+const PHONE: &[&str] = &[
+    "Uncategorized",
+    "Cellular",
+    "Cordless",
+    "Smartphone",
+    "Wired modem or voice gateway",
+    "Common ISDN access",
+];
+const LAN_LOAD: &[&str] = &[
+    "Fully available",
+    "1% to 17% utilized",
+    "17% to 33% utilized",
+    "33% to 50% utilized",
+    "50% to 67% utilized",
+    "67% to 83% utilized",
+    "83% to 99% utilized",
+    "No service available",
+];
+const AUDIO_VIDEO: &[&str] = &[
+    "Uncategorized",
+    "Wearable Headset Device",
+    "Hands-free Device",
+    "",
+    "Microphone",
+    "Loudspeaker",
+    "Headphones",
+    "Portable Audio",
+    "Car audio",
+    "Set-top box",
+    "HiFi Audio Device",
+    "VCR",
+    "Video Camera",
+    "Camcorder",
+    "Video Monitor",
+    "Video Display and Loudspeaker",
+    "Video Conferencing",
+    "",
+    "Gaming/Toy",
+];
+const PERIPHERAL_INPUT: &[&str] = &[
+    "",
+    "Keyboard",
+    "Pointing device",
+    "Combo keyboard/pointing device",
+];
+const PERIPHERAL: &[&str] = &[
+    "",
+    "Joystick",
+    "Gamepad",
+    "Remote control",
+    "Sensing device",
+    "Digitizer tablet",
+    "Card Reader",
+    "Digital Pen",
+    "Handheld scanner",
+    "Handheld gestural input device",
+];
+/// Imaging minor class bits, from bit 4 on.
+const IMAGING: &[&str] = &["Display", "Camera", "Scanner", "Printer"];
+const WEARABLE: &[&str] = &[
+    "Uncategorized",
+    "Wristwatch",
+    "Pager",
+    "Jacket",
+    "Helmet",
+    "Glasses",
+    "Pin",
+];
+const TOY: &[&str] = &[
+    "Uncategorized",
+    "Robot",
+    "Vehicle",
+    "Doll/Action figure",
+    "Controller",
+    "Game",
+];
+const HEALTH: &[&str] = &[
+    "Undefined",
+    "Blood Pressure Monitor",
+    "Thermometer",
+    "Weighing Scale",
+    "Glucose Meter",
+    "Pulse Oximeter",
+    "Heart/Pulse Rate Monitor",
+    "Health Data Display",
+    "Step Counter",
+    "Body Composition Analyzer",
+    "Peak Flow Monitor",
+    "Medication Monitor",
+    "Knee Prosthesis",
+    "Ankle Prosthesis",
+    "Generic Health Manager",
+    "Personal Mobility Device",
+];
+
+/// Class of Device of a BR/EDR device.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceClass {
+    pub service_classes: Vec<&'static str>,
+    pub major_class: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minor_class: Option<String>,
+}
+
+impl From<u32> for DeviceClass {
+    fn from(class: u32) -> Self {
+        let major = (class >> 8) & 0x1f;
+        let minor = ((class >> 2) & 0x3f) as usize;
+        let named = |names: &[&'static str], index: usize| {
+            names
+                .get(index)
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_string())
+        };
+
+        let (major_class, minor_class) = match major {
+            0x00 => ("Miscellaneous", None),
+            0x01 => ("Computer", named(COMPUTER, minor)),
+            0x02 => ("Phone", named(PHONE, minor)),
+            0x03 => ("LAN/Network Access point", named(LAN_LOAD, minor >> 3)),
+            0x04 => ("Audio/Video", named(AUDIO_VIDEO, minor)),
+            0x05 => {
+                let parts = [
+                    named(PERIPHERAL_INPUT, minor >> 4),
+                    named(PERIPHERAL, minor & 0x0f),
+                ];
+                let parts: Vec<_> = parts.into_iter().flatten().collect();
+                ("Peripheral", (!parts.is_empty()).then(|| parts.join(", ")))
+            }
+            0x06 => {
+                let parts: Vec<_> = IMAGING
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| minor & (1 << (bit + 2)) != 0)
+                    .map(|(_, name)| *name)
+                    .collect();
+                ("Imaging", (!parts.is_empty()).then(|| parts.join(", ")))
+            }
+            0x07 => ("Wearable", named(WEARABLE, minor)),
+            0x08 => ("Toy", named(TOY, minor)),
+            0x09 => ("Health", named(HEALTH, minor)),
+            0x1f => ("Uncategorized", None),
+            _ => ("Reserved", None),
+        };
+
+        Self {
+            service_classes: SERVICE_CLASSES
+                .iter()
+                .filter(|(bit, _)| class & (1 << bit) != 0)
+                .map(|(_, name)| *name)
+                .collect(),
+            major_class,
+            minor_class,
+        }
+    }
+}
+
+impl DeviceClass {
+    pub fn labels(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("major_class", self.major_class.to_owned()),
+            ("minor_class", self.minor_class.clone().unwrap_or_default()),
+        ]
+    }
+}
@@ -0,0 +1,51 @@
+use openssl::cipher::Cipher;
+use openssl::cipher_ctx::CipherCtx;
+use openssl::error::ErrorStack;
+
+use crate::config::Key;
+
+/// Decrypt and authenticate AES-128-CCM data.
+///
+/// `symm::decrypt_aead` sets the tag after the key, which makes OpenSSL keep the
+/// default tag length, so the short tags used in advertisements never verify.
+pub fn decrypt_ccm(
+    key: &Key,
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, ErrorStack> {
+    let mut ctx = CipherCtx::new()?;
+    ctx.decrypt_init(Some(Cipher::aes_128_ccm()), None, None)?;
+    ctx.set_iv_length(nonce.len())?;
+    ctx.set_tag(tag)?;
+    ctx.decrypt_init(None, Some(&key.0), Some(nonce))?;
+    ctx.set_data_len(ciphertext.len())?;
+    if !aad.is_empty() {
+        ctx.cipher_update(aad, None)?;
+    }
+    let mut plaintext = vec![];
+    ctx.cipher_update_vec(ciphertext, &mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Encrypt a single block with AES-128, the security function `e` of the Core Specification.
+pub fn encrypt_block(key: &[u8; 16], block: &[u8; 16]) -> Result<[u8; 16], ErrorStack> {
+    let mut ctx = CipherCtx::new()?;
+    ctx.encrypt_init(Some(Cipher::aes_128_ecb()), Some(key), None)?;
+    ctx.set_padding(false);
+    let mut ciphertext = vec![];
+    ctx.cipher_update_vec(block, &mut ciphertext)?;
+    ctx.cipher_final_vec(&mut ciphertext)?;
+    Ok(ciphertext.try_into().unwrap())
+}
+
+/// Decrypt AES-128-CTR data, starting with the counter block `iv`.
+pub fn decrypt_ctr(key: &Key, iv: &[u8; 16], ciphertext: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut ctx = CipherCtx::new()?;
+    ctx.decrypt_init(Some(Cipher::aes_128_ctr()), Some(&key.0), Some(iv))?;
+    let mut plaintext = vec![];
+    ctx.cipher_update_vec(ciphertext, &mut plaintext)?;
+    ctx.cipher_final_vec(&mut plaintext)?;
+    Ok(plaintext)
+}
@@ -0,0 +1,260 @@
+use bluer::{Uuid, UuidExt};
+use serde::Serialize;
+
+use super::Reading;
+
+/// Service data UUID of Eddystone frames.
+pub const SERVICE_UUID: u16 = 0xfeaa;
+
+const FRAME_UID: u8 = 0x00;
+const FRAME_URL: u8 = 0x10;
+const FRAME_TLM: u8 = 0x20;
+const FRAME_EID: u8 = 0x30;
+
+const TLM_UNENCRYPTED: u8 = 0x00;
+/// Beacon temperature value meaning the sensor is not supported.
+const TEMPERATURE_UNSUPPORTED: i16 = -0x8000;
+
+const URL_SCHEMES: &[&str] = &["http://www.", "https://www.", "http://", "https://"];
+const URL_EXPANSIONS: &[&str] = &[
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+pub fn service_uuid() -> Uuid {
+    Uuid::from_u16(SERVICE_UUID)
+}
+
+/// A single Eddystone frame.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "frame", rename_all = "snake_case")]
+pub enum Eddystone {
+    Uid {
+        /// Calibrated TX power at 0m, in dBm.
+        tx_power: i8,
+        namespace: String,
+        instance: String,
+    },
+    Url {
+        tx_power: i8,
+        url: String,
+    },
+    Tlm(Telemetry),
+    Eid {
+        tx_power: i8,
+        eid: String,
+    },
+}
+
+/// Unencrypted telemetry frame.
+#[derive(Debug, Clone, Serialize)]
+pub struct Telemetry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_volts: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_celsius: Option<f64>,
+    pub advertisement_count: u32,
+    pub uptime_seconds: f64,
+}
+
+impl Eddystone {
+    /// Parse the service data of UUID [`SERVICE_UUID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match *data.first()? {
+            FRAME_UID if data.len() >= 18 => Some(Self::Uid {
+                tx_power: data[1] as i8,
+                namespace: hex(&data[2..12]),
+                instance: hex(&data[12..18]),
+            }),
+            FRAME_URL if data.len() >= 3 => Some(Self::Url {
+                tx_power: data[1] as i8,
+                url: url(data[2], &data[3..])?,
+            }),
+            FRAME_TLM if data.len() >= 14 && data[1] == TLM_UNENCRYPTED => {
+                let battery = u16::from_be_bytes([data[2], data[3]]);
+                let temperature = i16::from_be_bytes([data[4], data[5]]);
+                Some(Self::Tlm(Telemetry {
+                    battery_volts: (battery != 0).then_some(battery as f64 / 1000.0),
+                    temperature_celsius: (temperature != TEMPERATURE_UNSUPPORTED)
+                        .then_some(temperature as f64 / 256.0),
+                    advertisement_count: u32::from_be_bytes(data[6..10].try_into().unwrap()),
+                    uptime_seconds: u32::from_be_bytes(data[10..14].try_into().unwrap()) as f64
+                        / 10.0,
+                }))
+            }
+            FRAME_EID if data.len() >= 10 => Some(Self::Eid {
+                tx_power: data[1] as i8,
+                eid: hex(&data[2..10]),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn telemetry(&self) -> Option<&Telemetry> {
+        match self {
+            Self::Tlm(telemetry) => Some(telemetry),
+            _ => None,
+        }
+    }
+}
+
+impl Telemetry {
+    pub fn readings(&self) -> Vec<Reading> {
+        let mut readings = vec![];
+        if let Some(volts) = self.battery_volts {
+            readings.push(Reading::new(
+                "bluetooth_eddystone_battery_volts",
+                "The battery voltage reported in Eddystone telemetry.",
+                "volts",
+                volts,
+            ));
+        }
+        if let Some(celsius) = self.temperature_celsius {
+            readings.push(Reading::new(
+                "bluetooth_eddystone_temperature_celsius",
+                "The beacon temperature reported in Eddystone telemetry.",
+                "celsius",
+                celsius,
+            ));
+        }
+        readings.push(Reading::new(
+            "bluetooth_eddystone_advertisements",
+            "The number of advertisements sent since the Eddystone beacon powered up.",
+            "",
+            self.advertisement_count.into(),
+        ));
+        readings.push(Reading::new(
+            "bluetooth_eddystone_uptime_seconds",
+            "The time since the Eddystone beacon powered up.",
+            "seconds",
+            self.uptime_seconds,
+        ));
+        readings
+    }
+}
+
+fn url(scheme: u8, encoded: &[u8]) -> Option<String> {
+    let mut url = URL_SCHEMES.get(scheme as usize)?.to_string();
+    for &byte in encoded {
+        match URL_EXPANSIONS.get(byte as usize) {
+            Some(expansion) => url.push_str(expansion),
+            None if (0x21..0x7f).contains(&byte) => url.push(byte as char),
+            None => return None,
+        }
+    }
+    Some(url)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UID: [u8; 20] = [
+        0x00, 0xe7, // UID frame, -25 dBm
+        0x8b, 0x74, 0x5a, 0x1b, 0x2b, 0x67, 0x4d, 0x9e, 0x6c, 0x10, // namespace
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x2a, // instance
+        0x00, 0x00, // reserved
+    ];
+    const TLM: [u8; 14] = [
+        0x20, 0x00, // unencrypted TLM frame
+        0x0b, 0xb8, // 3000 mV
+        0x18, 0x80, // 24.5 °C
+        0x00, 0x00, 0x01, 0x00, // 256 advertisements
+        0x00, 0x00, 0x0e, 0x10, // 360 seconds
+    ];
+
+    #[test]
+    fn parses_uid() {
+        match Eddystone::parse(&UID) {
+            Some(Eddystone::Uid {
+                tx_power,
+                namespace,
+                instance,
+            }) => {
+                assert_eq!(tx_power, -25);
+                assert_eq!(namespace, "8b745a1b2b674d9e6c10");
+                assert_eq!(instance, "00000000002a");
+            }
+            other => panic!("expected a UID frame, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expands_urls() {
+        let url = |data: &[u8]| match Eddystone::parse(data) {
+            Some(Eddystone::Url { url, .. }) => url,
+            other => panic!("expected a URL frame, got {:?}", other),
+        };
+        assert_eq!(
+            url(&[0x10, 0xeb, 0x01, b'g', b'o', b'o', b'g', b'l', b'e', 0x00]),
+            "https://www.google.com/"
+        );
+        assert_eq!(
+            url(&[0x10, 0xeb, 0x02, b'e', b'x', 0x08, b'/', b'a']),
+            "http://ex.org/a"
+        );
+        assert_eq!(url(&[0x10, 0xeb, 0x00]), "http://www.");
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        // unknown scheme
+        assert!(Eddystone::parse(&[0x10, 0xeb, 0x04, b'a']).is_none());
+        // neither an expansion nor a printable character
+        assert!(Eddystone::parse(&[0x10, 0xeb, 0x03, b'a', 0x20]).is_none());
+    }
+
+    #[test]
+    fn parses_tlm() {
+        let telemetry = Eddystone::parse(&TLM)
+            .unwrap()
+            .telemetry()
+            .cloned()
+            .unwrap();
+        assert_eq!(telemetry.battery_volts, Some(3.0));
+        assert_eq!(telemetry.temperature_celsius, Some(24.5));
+        assert_eq!(telemetry.advertisement_count, 256);
+        assert_eq!(telemetry.uptime_seconds, 360.0);
+        assert_eq!(telemetry.readings().len(), 4);
+    }
+
+    #[test]
+    fn leaves_out_unsupported_tlm_values() {
+        let mut data = TLM;
+        data[2..6].copy_from_slice(&[0x00, 0x00, 0x80, 0x00]);
+        let telemetry = Eddystone::parse(&data)
+            .unwrap()
+            .telemetry()
+            .cloned()
+            .unwrap();
+        assert_eq!(telemetry.battery_volts, None);
+        assert_eq!(telemetry.temperature_celsius, None);
+        assert_eq!(telemetry.readings().len(), 2);
+    }
+
+    #[test]
+    fn rejects_encrypted_tlm() {
+        let mut data = TLM;
+        data[1] = 0x01;
+        assert!(Eddystone::parse(&data).is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_frames() {
+        let eid = [0x30, 0xe7, 1, 2, 3, 4, 5, 6, 7, 8];
+        for frame in [&UID[..18], &TLM[..], &eid[..], &[0x10, 0xeb, 0x00]] {
+            assert!(Eddystone::parse(frame).is_some());
+            for len in 0..frame.len() {
+                assert!(
+                    Eddystone::parse(&frame[..len]).is_none(),
+                    "{:02x?}",
+                    &frame[..len]
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use bluer::{Uuid, UuidExt};
+use serde::Serialize;
+
+/// Service data UUID of Exposure Notification advertisements.
+pub const SERVICE_UUID: u16 = 0xfd6f;
+
+const LEN: usize = 20;
+
+pub fn service_uuid() -> Uuid {
+    Uuid::from_u16(SERVICE_UUID)
+}
+
+/// An Exposure Notification advertisement of a phone.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExposureNotification {
+    /// Changes along with the address, hex encoded.
+    pub rolling_proximity_identifier: String,
+    /// Encrypted metadata, hex encoded.
+    pub metadata: String,
+}
+
+impl ExposureNotification {
+    /// Parse the service data of UUID [`SERVICE_UUID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != LEN {
+            return None;
+        }
+        Some(Self {
+            rolling_proximity_identifier: hex(&data[..16]),
+            metadata: hex(&data[16..]),
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [u8; 20] = [
+        0x6b, 0x0f, 0x2d, 0x8e, 0x91, 0x3a, 0x54, 0xc7, // rolling proximity identifier
+        0x02, 0xe8, 0x7d, 0x16, 0xa5, 0x40, 0xbb, 0x19, //
+        0x5e, 0x33, 0xa2, 0x71, // associated encrypted metadata
+    ];
+
+    #[test]
+    fn parses_advertisement() {
+        let notification = ExposureNotification::parse(&DATA).unwrap();
+        assert_eq!(
+            notification.rolling_proximity_identifier,
+            "6b0f2d8e913a54c702e87d16a540bb19"
+        );
+        assert_eq!(notification.metadata, "5e33a271");
+    }
+
+    #[test]
+    fn rejects_other_lengths() {
+        assert!(ExposureNotification::parse(&DATA[..19]).is_none());
+        assert!(ExposureNotification::parse(&[DATA.as_slice(), &[0]].concat()).is_none());
+        assert!(ExposureNotification::parse(&[]).is_none());
+    }
+}
@@ -0,0 +1,55 @@
+use bluer::{Uuid, UuidExt};
+use serde::Serialize;
+
+/// Service data UUID of Google Fast Pair advertisements.
+pub const SERVICE_UUID: u16 = 0xfe2c;
+
+const MODEL_ID_LEN: usize = 3;
+
+pub fn service_uuid() -> Uuid {
+    Uuid::from_u16(SERVICE_UUID)
+}
+
+/// A Google Fast Pair accessory.
+#[derive(Debug, Clone, Serialize)]
+pub struct FastPair {
+    /// Only sent while the accessory is discoverable, otherwise it sends account key data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<u32>,
+}
+
+impl FastPair {
+    /// Parse the service data of UUID [`SERVICE_UUID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.is_empty() {
+            return None;
+        }
+        let model_id = (data.len() == MODEL_ID_LEN)
+            .then(|| u32::from_be_bytes([0, data[0], data[1], data[2]]));
+        Some(Self { model_id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_model_id() {
+        let fast_pair = FastPair::parse(&[0xf5, 0x24, 0x94]).unwrap();
+        assert_eq!(fast_pair.model_id, Some(0xf52494));
+    }
+
+    #[test]
+    fn parses_account_key_data_without_model_id() {
+        // flags, account key filter of 4 bytes and salt
+        let data = [0x00, 0x40, 0x0a, 0x97, 0x3c, 0x51, 0x11, 0x5c, 0x3f];
+        let fast_pair = FastPair::parse(&data).unwrap();
+        assert_eq!(fast_pair.model_id, None);
+    }
+
+    #[test]
+    fn rejects_empty_data() {
+        assert!(FastPair::parse(&[]).is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+
+use super::SensorReading;
+
+/// Company identifier Govee thermo-hygrometers send their readings with.
+pub const COMPANY_ID: u16 = 0xec88;
+/// Company identifier of the H5179, sent as `01 88`. It is not assigned to Govee,
+/// so it is only trusted with a matching name.
+pub const H5179_COMPANY_ID: u16 = 0x8801;
+
+const H5075_LEN: usize = 6;
+const H5074_LEN: usize = 7;
+const H5179_LEN: usize = 9;
+
+/// Temperature and humidity are packed into one number of the H5075's data.
+const H5075_NEGATIVE: u32 = 0x800000;
+
+#[derive(Debug, Clone, Serialize)]
+pub enum Model {
+    H5074,
+    H5075,
+    H5179,
+}
+
+/// Readings of a Govee thermo-hygrometer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Govee {
+    pub model: Model,
+    pub readings: Vec<SensorReading>,
+}
+
+impl Govee {
+    /// Parse the manufacturer data of company [`COMPANY_ID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            H5075_LEN => {
+                let packed = u32::from_be_bytes([0, data[1], data[2], data[3]]);
+                let value = (packed & !H5075_NEGATIVE) as f64;
+                let mut temperature = (value / 1000.0).trunc() / 10.0;
+                if packed & H5075_NEGATIVE != 0 {
+                    temperature = -temperature;
+                }
+                Some(Self::new(
+                    Model::H5075,
+                    temperature,
+                    value % 1000.0 / 10.0,
+                    data[4],
+                ))
+            }
+            H5074_LEN => Some(Self::little_endian(Model::H5074, &data[1..6])),
+            _ => None,
+        }
+    }
+
+    /// Parse the manufacturer data of company [`H5179_COMPANY_ID`] of a device called `name`.
+    pub fn parse_h5179(data: &[u8], name: &str) -> Option<Self> {
+        if data.len() != H5179_LEN || !name.contains("H5179") {
+            return None;
+        }
+        Some(Self::little_endian(Model::H5179, &data[4..9]))
+    }
+
+    /// Temperature and humidity in hundredths, followed by the battery level.
+    fn little_endian(model: Model, data: &[u8]) -> Self {
+        Self::new(
+            model,
+            i16::from_le_bytes([data[0], data[1]]) as f64 / 100.0,
+            u16::from_le_bytes([data[2], data[3]]) as f64 / 100.0,
+            data[4],
+        )
+    }
+
+    fn new(model: Model, temperature: f64, humidity: f64, battery: u8) -> Self {
+        Self {
+            model,
+            readings: vec![
+                SensorReading::new("temperature", "celsius", temperature),
+                SensorReading::new("humidity", "percent", humidity),
+                SensorReading::new("battery", "percent", battery.into()),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(govee: &Govee) -> Vec<(&'static str, f64)> {
+        govee
+            .readings
+            .iter()
+            .map(|reading| (reading.kind, reading.value))
+            .collect()
+    }
+
+    #[test]
+    fn parses_h5075() {
+        // 23.8 °C, 51.0 %, 92 % battery
+        let govee = Govee::parse(&[0x00, 0x03, 0xa3, 0xae, 0x5c, 0x00]).unwrap();
+        assert!(matches!(govee.model, Model::H5075));
+        assert_eq!(
+            readings(&govee),
+            [("temperature", 23.8), ("humidity", 51.0), ("battery", 92.0)]
+        );
+    }
+
+    #[test]
+    fn parses_negative_h5075_temperature() {
+        // -8.0 °C, 42.9 %, 100 % battery
+        let govee = Govee::parse(&[0x00, 0x81, 0x3a, 0x2d, 0x64, 0x00]).unwrap();
+        assert_eq!(
+            readings(&govee),
+            [
+                ("temperature", -8.0),
+                ("humidity", 42.9),
+                ("battery", 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_h5074() {
+        // 25.45 °C, 67.3 %, 100 % battery
+        let govee = Govee::parse(&[0x00, 0xf1, 0x09, 0x4a, 0x1a, 0x64, 0x02]).unwrap();
+        assert!(matches!(govee.model, Model::H5074));
+        assert_eq!(
+            readings(&govee),
+            [
+                ("temperature", 25.45),
+                ("humidity", 67.3),
+                ("battery", 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_h5179_by_name() {
+        // 27.88 °C, 44.14 %, 100 % battery
+        let data = [0xec, 0x00, 0x01, 0x01, 0xe4, 0x0a, 0x3e, 0x11, 0x64];
+        let govee = Govee::parse_h5179(&data, "Govee_H5179_6A2B").unwrap();
+        assert!(matches!(govee.model, Model::H5179));
+        assert_eq!(
+            readings(&govee),
+            [
+                ("temperature", 27.88),
+                ("humidity", 44.14),
+                ("battery", 100.0)
+            ]
+        );
+        assert!(Govee::parse_h5179(&data, "Unrelated").is_none());
+    }
+
+    #[test]
+    fn rejects_other_lengths() {
+        assert!(Govee::parse(&[0x00, 0x03, 0xa3]).is_none());
+        assert!(Govee::parse(&[]).is_none());
+    }
+}
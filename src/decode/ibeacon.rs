@@ -0,0 +1,109 @@
+use bluer::Uuid;
+use serde::Serialize;
+
+use super::Reading;
+
+/// Apple's Bluetooth SIG company identifier.
+pub const COMPANY_ID: u16 = 0x004c;
+
+/// Labels identifying a beacon.
+pub const LABELS: [&str; 3] = ["ibeacon_uuid", "ibeacon_major", "ibeacon_minor"];
+
+const BEACON_TYPE: u8 = 0x02;
+const BEACON_LENGTH: u8 = 0x15;
+
+/// An Apple iBeacon advertisement.
+#[derive(Debug, Clone, Serialize)]
+pub struct IBeacon {
+    pub uuid: Uuid,
+    pub major: u16,
+    pub minor: u16,
+    /// Calibrated RSSI at one meter, in dBm.
+    pub measured_power: i8,
+}
+
+impl IBeacon {
+    /// Parse the manufacturer data of company [`COMPANY_ID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != 23 || data[0] != BEACON_TYPE || data[1] != BEACON_LENGTH {
+            return None;
+        }
+
+        Some(Self {
+            uuid: Uuid::from_bytes(data[2..18].try_into().unwrap()),
+            major: u16::from_be_bytes([data[18], data[19]]),
+            minor: u16::from_be_bytes([data[20], data[21]]),
+            measured_power: data[22] as i8,
+        })
+    }
+
+    pub fn labels(&self) -> Vec<(&'static str, String)> {
+        let [uuid, major, minor] = LABELS;
+        vec![
+            (uuid, self.uuid.to_string()),
+            (major, self.major.to_string()),
+            (minor, self.minor.to_string()),
+        ]
+    }
+
+    pub fn readings(&self) -> Vec<Reading> {
+        vec![Reading::new(
+            "bluetooth_ibeacon_measured_power",
+            "The calibrated RSSI at one meter advertised by the iBeacon.",
+            "dBm",
+            self.measured_power.into(),
+        )
+        .labels(self.labels())
+        .single()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEACON: [u8; 23] = [
+        0x02, 0x15, // iBeacon
+        0xf7, 0x82, 0x6d, 0xa6, 0x4f, 0xa2, 0x4e, 0x98, // uuid
+        0x80, 0x24, 0xbc, 0x5b, 0x71, 0xe0, 0x89, 0x3e, //
+        0x04, 0xd2, // major
+        0x16, 0x2e, // minor
+        0xc5, // measured power
+    ];
+
+    #[test]
+    fn parses_ibeacon() {
+        let beacon = IBeacon::parse(&BEACON).unwrap();
+        assert_eq!(
+            beacon.uuid.to_string(),
+            "f7826da6-4fa2-4e98-8024-bc5b71e0893e"
+        );
+        assert_eq!(beacon.major, 1234);
+        assert_eq!(beacon.minor, 5678);
+        assert_eq!(beacon.measured_power, -59);
+        assert_eq!(
+            beacon.labels(),
+            [
+                (
+                    "ibeacon_uuid",
+                    "f7826da6-4fa2-4e98-8024-bc5b71e0893e".to_owned()
+                ),
+                ("ibeacon_major", "1234".to_owned()),
+                ("ibeacon_minor", "5678".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_other_apple_messages() {
+        // a Nearby Info message
+        assert!(IBeacon::parse(&[0x10, 0x05, 0x01, 0x18, 0x44, 0x6e, 0x9d]).is_none());
+        assert!(IBeacon::parse(&BEACON[..22]).is_none());
+        let mut longer = BEACON.to_vec();
+        longer.push(0x00);
+        assert!(IBeacon::parse(&longer).is_none());
+        let mut length = BEACON;
+        length[1] = 0x16;
+        assert!(IBeacon::parse(&length).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs;
+use std::path::Path;
+
+use bluer::Address;
+use lazy_static::lazy_static;
+use openssl::error::ErrorStack;
+
+use crate::config::{self, Key};
+
+use super::crypto;
+use super::AddressKind;
+
+/// Where BlueZ stores the keys of paired devices, as `<adapter>/<device>/info`.
+const BLUEZ_STORAGE: &str = "/var/lib/bluetooth";
+
+lazy_static! {
+    /// Identity Resolving Keys by identity, most significant byte first.
+    static ref KEYS: Vec<(String, [u8; 16])> = {
+        let mut keys: Vec<_> = config::CONFIG
+            .identities
+            .keys
+            .iter()
+            .map(|(name, key)| (name.clone(), reversed(key.0)))
+            .collect();
+        if config::CONFIG.identities.import_bluez {
+            keys.extend(import(Path::new(BLUEZ_STORAGE)));
+        }
+        keys
+    };
+}
+
+/// Name of the device a resolvable private address belongs to, if a known key resolves it.
+pub fn resolve(address: Address, kind: AddressKind) -> Option<String> {
+    if kind != AddressKind::Resolvable {
+        return None;
+    }
+    // the address is prand followed by hash, both most significant byte first
+    let (prand, hash) = address.0.split_at(3);
+    KEYS.iter()
+        .find(|(_, key)| match ah(key, prand.try_into().unwrap()) {
+            Ok(expected) => expected == hash,
+            Err(err) => {
+                log::error!("failed to resolve {}: {}", address, err);
+                false
+            }
+        })
+        .map(|(name, _)| name.clone())
+}
+
+/// The random address hash function of the Core specification, Vol 3, Part H, 2.2.2.
+fn ah(key: &[u8; 16], prand: [u8; 3]) -> Result<[u8; 3], ErrorStack> {
+    let mut block = [0; 16];
+    block[13..].copy_from_slice(&prand);
+    let hash = crypto::encrypt_block(key, &block)?;
+    Ok(hash[13..].try_into().unwrap())
+}
+
+/// Read the keys of all devices paired with any adapter, named like BlueZ names them.
+fn import(storage: &Path) -> Vec<(String, [u8; 16])> {
+    let infos = fs::read_dir(storage)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .flat_map(|adapter| fs::read_dir(adapter.path()).into_iter().flatten().flatten())
+        .map(|device| device.path().join("info"));
+
+    let mut keys = vec![];
+    for path in infos {
+        let info = match fs::read_to_string(&path) {
+            Ok(info) => info,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("failed to read {}: {}", path.display(), err);
+                }
+                continue;
+            }
+        };
+        let key = match value(&info, "IdentityResolvingKey", "Key")
+            .and_then(|hex| hex.parse::<Key>().ok())
+        {
+            Some(key) => key,
+            None => continue,
+        };
+        // the identity address of the device is the name of its directory
+        let address = path.parent().and_then(Path::file_name);
+        let name = value(&info, "General", "Alias")
+            .or_else(|| value(&info, "General", "Name"))
+            .map(str::to_owned)
+            .or_else(|| address.map(|address| address.to_string_lossy().into_owned()));
+        if let Some(name) = name {
+            log::info!("imported the identity resolving key of {}", name);
+            keys.push((name, reversed(key.0)));
+        }
+    }
+    if keys.is_empty() {
+        log::warn!("no identity resolving keys found in {}", storage.display());
+    }
+    keys
+}
+
+/// Value of `key` in `section` of a BlueZ info file.
+fn value<'a>(info: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let header = format!("[{}]", section);
+    info.lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            (name.trim() == key).then(|| value.trim())
+        })
+}
+
+/// BlueZ stores keys least significant byte first, AES takes them the other way round.
+fn reversed(mut key: [u8; 16]) -> [u8; 16] {
+    key.reverse();
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample data of the Core specification, Vol 3, Part H, D.7.
+    const IRK: [u8; 16] = [
+        0xec, 0x02, 0x34, 0xa3, 0x57, 0xc8, 0xad, 0x05, 0x34, 0x10, 0x10, 0xa6, 0x0a, 0x39, 0x7d,
+        0x9b,
+    ];
+    const PRAND: [u8; 3] = [0x70, 0x81, 0x94];
+    const HASH: [u8; 3] = [0x0d, 0xfb, 0xaa];
+
+    #[test]
+    fn hashes_sample_data() {
+        assert_eq!(ah(&IRK, PRAND).unwrap(), HASH);
+    }
+
+    #[test]
+    fn reverses_stored_keys() {
+        // BlueZ and the configuration store the sample key the other way round
+        let stored: Key = "9b7d390aa610103405adc857a33402ec".parse().unwrap();
+        assert_eq!(reversed(stored.0), IRK);
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+
+/// Microsoft's Bluetooth SIG company identifier.
+pub const COMPANY_ID: u16 = 0x0006;
+
+const CDP_BEACON: u8 = 0x01;
+const SWIFT_PAIR: u8 = 0x03;
+
+/// Device types of Connected Devices Platform beacons.
+const DEVICE_TYPES: &[(u8, &str)] = &[
+    (1, "Xbox One"),
+    (6, "iPhone"),
+    (7, "iPad"),
+    (8, "Android"),
+    (9, "Windows Desktop"),
+    (11, "Windows Phone"),
+    (12, "Linux"),
+    (13, "Windows IoT"),
+    (14, "Surface Hub"),
+    (15, "Windows Laptop"),
+    (16, "Windows Tablet"),
+];
+
+/// A Microsoft beacon.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "scenario", rename_all = "snake_case")]
+pub enum Microsoft {
+    /// Connected Devices Platform, sent by devices signed in to a Microsoft account.
+    Cdp {
+        device_type: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        device_type_name: Option<&'static str>,
+    },
+    /// Accessories waiting to be paired with Windows.
+    SwiftPair,
+}
+
+impl Microsoft {
+    /// Parse the manufacturer data of company [`COMPANY_ID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match *data.first()? {
+            CDP_BEACON => {
+                let device_type = data.get(1)? & 0x1f;
+                Some(Self::Cdp {
+                    device_type,
+                    device_type_name: DEVICE_TYPES
+                        .iter()
+                        .find(|(id, _)| *id == device_type)
+                        .map(|(_, name)| *name),
+                })
+            }
+            SWIFT_PAIR => Some(Self::SwiftPair),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cdp_beacon() {
+        // a Windows desktop, with the version bits above the device type
+        let data = [0x01, 0x29, 0x22, 0x00, 0x1d, 0x3f, 0x9f, 0x82, 0x12];
+        match Microsoft::parse(&data) {
+            Some(Microsoft::Cdp {
+                device_type,
+                device_type_name,
+            }) => {
+                assert_eq!(device_type, 9);
+                assert_eq!(device_type_name, Some("Windows Desktop"));
+            }
+            other => panic!("expected a CDP beacon, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn leaves_unknown_device_types_unnamed() {
+        assert!(matches!(
+            Microsoft::parse(&[0x01, 0x02]),
+            Some(Microsoft::Cdp {
+                device_type: 2,
+                device_type_name: None
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_swift_pair() {
+        let data = [0x03, 0x00, 0x80, 0x4d, 0x6f, 0x75, 0x73, 0x65];
+        assert!(matches!(
+            Microsoft::parse(&data),
+            Some(Microsoft::SwiftPair)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_and_truncated_beacons() {
+        assert!(Microsoft::parse(&[]).is_none());
+        assert!(Microsoft::parse(&[0x01]).is_none());
+        assert!(Microsoft::parse(&[0x05, 0x00]).is_none());
+    }
+}
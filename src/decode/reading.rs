@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// A single value to publish as a metric.
+///
+/// Readings of the same name must always carry the same label names, label
+/// values may be empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reading {
+    pub name: &'static str,
+    #[serde(skip)]
+    pub help: &'static str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    pub unit: &'static str,
+    pub value: f64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<&'static str, String>,
+    /// Whether a device has a single series of the reading, its labels describe
+    /// the device and a series with other label values replaces the previous one.
+    #[serde(skip)]
+    pub single: bool,
+}
+
+impl Reading {
+    pub fn new(name: &'static str, help: &'static str, unit: &'static str, value: f64) -> Self {
+        Self {
+            name,
+            help,
+            unit,
+            value,
+            labels: BTreeMap::new(),
+            single: false,
+        }
+    }
+
+    pub fn label(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.labels.insert(name, value.into());
+        self
+    }
+
+    pub fn labels(mut self, labels: Vec<(&'static str, String)>) -> Self {
+        self.labels.extend(labels);
+        self
+    }
+
+    pub fn single(mut self) -> Self {
+        self.single = true;
+        self
+    }
+}
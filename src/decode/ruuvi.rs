@@ -0,0 +1,267 @@
+use serde::Serialize;
+
+use super::Reading;
+
+/// Ruuvi Innovations company id of the manufacturer data.
+pub const COMPANY_ID: u16 = 0x0499;
+
+const FORMAT_RAWV1: u8 = 3;
+const FORMAT_RAWV2: u8 = 5;
+
+/// Sensor readings of a RuuviTag, fields the tag marks as invalid are left out.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Ruuvi {
+    pub format: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_celsius: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub humidity_percent: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pressure_pascals: Option<f64>,
+    /// Acceleration along the x, y and z axis, in g.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acceleration: Option<[f64; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_volts: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_power: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub movement_counter: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub measurement_sequence: Option<u16>,
+}
+
+impl Ruuvi {
+    /// Parse the manufacturer data of company [`COMPANY_ID`].
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        match *data.first()? {
+            FORMAT_RAWV1 if data.len() >= 14 => Some(Self::rawv1(data)),
+            FORMAT_RAWV2 if data.len() >= 18 => Some(Self::rawv2(data)),
+            _ => None,
+        }
+    }
+
+    /// Data format 3, see <https://docs.ruuvi.com/communication/bluetooth-advertisements/data-format-3-rawv1>.
+    fn rawv1(data: &[u8]) -> Self {
+        let integer = (data[2] & 0x7f) as f64 + data[3] as f64 / 100.0;
+        let temperature = if data[2] & 0x80 != 0 {
+            -integer
+        } else {
+            integer
+        };
+        Self {
+            format: FORMAT_RAWV1,
+            temperature_celsius: Some(temperature),
+            humidity_percent: Some(data[1] as f64 / 2.0),
+            pressure_pascals: Some(u16_at(data, 4) as f64 + 50000.0),
+            acceleration: Some([
+                i16_at(data, 6) as f64 / 1000.0,
+                i16_at(data, 8) as f64 / 1000.0,
+                i16_at(data, 10) as f64 / 1000.0,
+            ]),
+            battery_volts: Some(u16_at(data, 12) as f64 / 1000.0),
+            ..Default::default()
+        }
+    }
+
+    /// Data format 5, see <https://docs.ruuvi.com/communication/bluetooth-advertisements/data-format-5-rawv2>.
+    fn rawv2(data: &[u8]) -> Self {
+        let temperature = i16_at(data, 1);
+        let humidity = u16_at(data, 3);
+        let pressure = u16_at(data, 5);
+        let acceleration = [i16_at(data, 7), i16_at(data, 9), i16_at(data, 11)];
+        let power = u16_at(data, 13);
+        let battery = power >> 5;
+        let tx_power = power & 0x1f;
+        let sequence = u16_at(data, 16);
+        Self {
+            format: FORMAT_RAWV2,
+            temperature_celsius: (temperature != i16::MIN).then_some(temperature as f64 * 0.005),
+            humidity_percent: (humidity != u16::MAX).then_some(humidity as f64 * 0.0025),
+            pressure_pascals: (pressure != u16::MAX).then_some(pressure as f64 + 50000.0),
+            acceleration: (!acceleration.contains(&i16::MIN))
+                .then_some(acceleration.map(|milli_g| milli_g as f64 / 1000.0)),
+            battery_volts: (battery != 0x7ff).then_some((battery as f64 + 1600.0) / 1000.0),
+            tx_power: (tx_power != 0x1f).then_some(tx_power as i8 * 2 - 40),
+            movement_counter: (data[15] != u8::MAX).then_some(data[15]),
+            measurement_sequence: (sequence != u16::MAX).then_some(sequence),
+        }
+    }
+
+    pub fn readings(&self) -> Vec<Reading> {
+        let values = [
+            (
+                "bluetooth_ruuvi_temperature_celsius",
+                "The temperature measured by the RuuviTag.",
+                "celsius",
+                self.temperature_celsius,
+            ),
+            (
+                "bluetooth_ruuvi_humidity_percent",
+                "The relative humidity measured by the RuuviTag.",
+                "percent",
+                self.humidity_percent,
+            ),
+            (
+                "bluetooth_ruuvi_pressure_pascals",
+                "The air pressure measured by the RuuviTag.",
+                "pascals",
+                self.pressure_pascals,
+            ),
+            (
+                "bluetooth_ruuvi_battery_volts",
+                "The battery voltage of the RuuviTag.",
+                "volts",
+                self.battery_volts,
+            ),
+            (
+                "bluetooth_ruuvi_tx_power_dbm",
+                "The transmit power configured on the RuuviTag.",
+                "dBm",
+                self.tx_power.map(f64::from),
+            ),
+            (
+                "bluetooth_ruuvi_movement_count",
+                "The movement counter of the RuuviTag, wrapping at 255.",
+                "",
+                self.movement_counter.map(f64::from),
+            ),
+            (
+                "bluetooth_ruuvi_measurement_sequence",
+                "The measurement sequence number of the RuuviTag, wrapping at 65535.",
+                "",
+                self.measurement_sequence.map(f64::from),
+            ),
+        ];
+        let mut readings: Vec<_> = values
+            .into_iter()
+            .filter_map(|(name, help, unit, value)| Some(Reading::new(name, help, unit, value?)))
+            .collect();
+        if let Some(acceleration) = self.acceleration {
+            for (axis, value) in ["x", "y", "z"].into_iter().zip(acceleration) {
+                readings.push(
+                    Reading::new(
+                        "bluetooth_ruuvi_acceleration_g",
+                        "The acceleration measured by the RuuviTag, in g.",
+                        "g",
+                        value,
+                    )
+                    .label("axis", axis),
+                );
+            }
+        }
+        readings
+    }
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn i16_at(data: &[u8], offset: usize) -> i16 {
+    i16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Manufacturer data from the test vectors of the Ruuvi documentation.
+    fn data(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.unwrap();
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn parses_rawv1() {
+        let ruuvi = Ruuvi::parse(&data("03291A1ECE1EFC18F94202CA0B53")).unwrap();
+        assert_eq!(ruuvi.format, FORMAT_RAWV1);
+        assert_close(ruuvi.humidity_percent, 20.5);
+        assert_close(ruuvi.temperature_celsius, 26.3);
+        assert_close(ruuvi.pressure_pascals, 102766.0);
+        assert_eq!(ruuvi.acceleration, Some([-1.0, -1.726, 0.714]));
+        assert_close(ruuvi.battery_volts, 2.899);
+    }
+
+    #[test]
+    fn parses_negative_rawv1_temperature() {
+        let ruuvi = Ruuvi::parse(&data("0300FF6300008001800180010000")).unwrap();
+        assert_close(ruuvi.temperature_celsius, -127.99);
+        assert_close(ruuvi.humidity_percent, 0.0);
+        assert_close(ruuvi.pressure_pascals, 50000.0);
+        assert_eq!(ruuvi.acceleration, Some([-32.767, -32.767, -32.767]));
+    }
+
+    #[test]
+    fn parses_rawv2() {
+        let ruuvi =
+            Ruuvi::parse(&data("0512FC5394C37C0004FFFC040CAC364200CDCBB8334C884F")).unwrap();
+        assert_eq!(ruuvi.format, FORMAT_RAWV2);
+        assert_close(ruuvi.temperature_celsius, 24.3);
+        assert_close(ruuvi.humidity_percent, 53.49);
+        assert_close(ruuvi.pressure_pascals, 100044.0);
+        assert_eq!(ruuvi.acceleration, Some([0.004, -0.004, 1.036]));
+        assert_close(ruuvi.battery_volts, 2.977);
+        assert_eq!(ruuvi.tx_power, Some(4));
+        assert_eq!(ruuvi.movement_counter, Some(66));
+        assert_eq!(ruuvi.measurement_sequence, Some(205));
+    }
+
+    #[test]
+    fn parses_rawv2_limits() {
+        let max = Ruuvi::parse(&data("057FFFFFFEFFFE7FFF7FFF7FFFFFDEFEFFFECBB8334C884F")).unwrap();
+        assert_close(max.temperature_celsius, 163.835);
+        assert_close(max.humidity_percent, 163.835);
+        assert_close(max.pressure_pascals, 115534.0);
+        assert_eq!(max.acceleration, Some([32.767, 32.767, 32.767]));
+        assert_close(max.battery_volts, 3.646);
+        assert_eq!(max.tx_power, Some(20));
+        assert_eq!(max.movement_counter, Some(254));
+        assert_eq!(max.measurement_sequence, Some(65534));
+
+        let min = Ruuvi::parse(&data("058001000000008001800180010000000000CBB8334C884F")).unwrap();
+        assert_close(min.temperature_celsius, -163.835);
+        assert_close(min.humidity_percent, 0.0);
+        assert_close(min.pressure_pascals, 50000.0);
+        assert_eq!(min.acceleration, Some([-32.767, -32.767, -32.767]));
+        assert_close(min.battery_volts, 1.6);
+        assert_eq!(min.tx_power, Some(-40));
+        assert_eq!(min.movement_counter, Some(0));
+        assert_eq!(min.measurement_sequence, Some(0));
+    }
+
+    #[test]
+    fn leaves_out_unavailable_rawv2_values() {
+        let ruuvi =
+            Ruuvi::parse(&data("058000FFFFFFFF800080008000FFFFFFFFFFFFFFFFFFFFFF")).unwrap();
+        assert!(ruuvi.temperature_celsius.is_none());
+        assert!(ruuvi.humidity_percent.is_none());
+        assert!(ruuvi.pressure_pascals.is_none());
+        assert!(ruuvi.acceleration.is_none());
+        assert!(ruuvi.battery_volts.is_none());
+        assert!(ruuvi.tx_power.is_none());
+        assert!(ruuvi.movement_counter.is_none());
+        assert!(ruuvi.measurement_sequence.is_none());
+        assert!(ruuvi.readings().is_empty());
+    }
+
+    #[test]
+    fn rejects_short_and_unknown_formats() {
+        assert!(Ruuvi::parse(&data("0512FC5394C37C0004FFFC04")).is_none());
+        assert!(Ruuvi::parse(&data("03291A1ECE1E")).is_none());
+        assert!(Ruuvi::parse(&data("0412FC5394C37C0004FFFC040CAC364200CD")).is_none());
+        assert!(Ruuvi::parse(&[]).is_none());
+    }
+}
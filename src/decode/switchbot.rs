@@ -0,0 +1,185 @@
+use bluer::{Uuid, UuidExt};
+use serde::Serialize;
+
+use super::SensorReading;
+
+/// Service data UUID of SwitchBot advertisements.
+pub const SERVICE_UUID: u16 = 0xfd3d;
+/// Company identifier of the manufacturer data, which carries the Outdoor Meter's readings.
+pub const COMPANY_ID: u16 = 0x0969;
+
+const METER: u8 = b'T';
+const METER_PLUS: u8 = b'i';
+const OUTDOOR_METER: u8 = b'w';
+const CONTACT: u8 = b'd';
+const MOTION: u8 = b's';
+
+const MOTION_DETECTED: u8 = 0x40;
+const CONTACT_OPEN: u8 = 0x02;
+const CONTACT_LIGHT: u8 = 0x01;
+const MOTION_LIGHT_INTENSITY: u8 = 0x03;
+const BRIGHT: u8 = 2;
+
+pub fn service_uuid() -> Uuid {
+    Uuid::from_u16(SERVICE_UUID)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Model {
+    Meter,
+    OutdoorMeter,
+    Contact,
+    Motion,
+}
+
+/// Readings of a SwitchBot sensor.
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchBot {
+    pub model: Model,
+    pub readings: Vec<SensorReading>,
+}
+
+impl SwitchBot {
+    /// Parse the service data of UUID [`SERVICE_UUID`], along with the
+    /// manufacturer data of company [`COMPANY_ID`] if the device sends any.
+    pub fn parse(data: &[u8], manufacturer_data: Option<&[u8]>) -> Option<Self> {
+        let battery = SensorReading::new("battery", "percent", (*data.get(2)? & 0x7f).into());
+        let flag = |byte: u8, mask: u8| if byte & mask != 0 { 1.0 } else { 0.0 };
+
+        let (model, readings) = match data[0] & 0x7f {
+            METER | METER_PLUS => {
+                let mut readings = climate(data.get(3..6)?);
+                readings.push(battery);
+                (Model::Meter, readings)
+            }
+            OUTDOOR_METER => {
+                let mut readings = climate(manufacturer_data?.get(8..11)?);
+                readings.push(battery);
+                (Model::OutdoorMeter, readings)
+            }
+            CONTACT if data.len() >= 9 => (
+                Model::Contact,
+                vec![
+                    SensorReading::new("opening", "", flag(data[3], CONTACT_OPEN)),
+                    SensorReading::new("motion", "", flag(data[1], MOTION_DETECTED)),
+                    SensorReading::new("light", "", flag(data[3], CONTACT_LIGHT)),
+                    SensorReading::new("count", "", (data[8] & 0x0f).into()),
+                    battery,
+                ],
+            ),
+            MOTION if data.len() >= 6 => {
+                let bright = data[5] & MOTION_LIGHT_INTENSITY == BRIGHT;
+                (
+                    Model::Motion,
+                    vec![
+                        SensorReading::new("motion", "", flag(data[1], MOTION_DETECTED)),
+                        SensorReading::new("light", "", if bright { 1.0 } else { 0.0 }),
+                        battery,
+                    ],
+                )
+            }
+            _ => return None,
+        };
+        Some(Self { model, readings })
+    }
+}
+
+/// Temperature in tenths and degrees with a sign bit, followed by the humidity.
+fn climate(data: &[u8]) -> Vec<SensorReading> {
+    let mut temperature = (data[1] & 0x7f) as f64 + (data[0] & 0x0f) as f64 / 10.0;
+    if data[1] & 0x80 == 0 {
+        temperature = -temperature;
+    }
+    vec![
+        SensorReading::new("temperature", "celsius", temperature),
+        SensorReading::new("humidity", "percent", (data[2] & 0x7f).into()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(switchbot: &SwitchBot) -> Vec<(&'static str, f64)> {
+        switchbot
+            .readings
+            .iter()
+            .map(|reading| (reading.kind, reading.value))
+            .collect()
+    }
+
+    #[test]
+    fn parses_meter() {
+        // 22.6 °C, 38 %, 100 % battery
+        let switchbot = SwitchBot::parse(&[0x54, 0x00, 0x64, 0x06, 0x96, 0x26], None).unwrap();
+        assert!(matches!(switchbot.model, Model::Meter));
+        assert_eq!(
+            readings(&switchbot),
+            [
+                ("temperature", 22.6),
+                ("humidity", 38.0),
+                ("battery", 100.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_negative_meter_temperature() {
+        let switchbot = SwitchBot::parse(&[0x69, 0x00, 0x50, 0x03, 0x05, 0x40], None).unwrap();
+        assert_eq!(
+            readings(&switchbot),
+            [("temperature", -5.3), ("humidity", 64.0), ("battery", 80.0)]
+        );
+    }
+
+    #[test]
+    fn parses_outdoor_meter() {
+        // the readings are in the manufacturer data, after the MAC and two more bytes
+        let manufacturer_data = [
+            0xd4, 0x2f, 0x1b, 0x3c, 0x5e, 0xf1, 0x0e, 0x00, 0x05, 0x96, 0x3a,
+        ];
+        let switchbot = SwitchBot::parse(&[0x77, 0x00, 0x5f], Some(&manufacturer_data)).unwrap();
+        assert!(matches!(switchbot.model, Model::OutdoorMeter));
+        assert_eq!(
+            readings(&switchbot),
+            [("temperature", 22.5), ("humidity", 58.0), ("battery", 95.0)]
+        );
+        assert!(SwitchBot::parse(&[0x77, 0x00, 0x5f], None).is_none());
+    }
+
+    #[test]
+    fn parses_contact_sensor() {
+        let data = [0x64, 0x40, 0x5a, 0x03, 0x00, 0x10, 0x00, 0x12, 0x03];
+        let switchbot = SwitchBot::parse(&data, None).unwrap();
+        assert!(matches!(switchbot.model, Model::Contact));
+        assert_eq!(
+            readings(&switchbot),
+            [
+                ("opening", 1.0),
+                ("motion", 1.0),
+                ("light", 1.0),
+                ("count", 3.0),
+                ("battery", 90.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_motion_sensor() {
+        let data = [0x73, 0x40, 0x64, 0x00, 0x24, 0x02];
+        let switchbot = SwitchBot::parse(&data, None).unwrap();
+        assert!(matches!(switchbot.model, Model::Motion));
+        assert_eq!(
+            readings(&switchbot),
+            [("motion", 1.0), ("light", 1.0), ("battery", 100.0)]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert!(SwitchBot::parse(&[0x54, 0x00], None).is_none());
+        assert!(SwitchBot::parse(&[0x54, 0x00, 0x64, 0x06], None).is_none());
+        assert!(SwitchBot::parse(&[0x64, 0x40, 0x5a, 0x03], None).is_none());
+    }
+}
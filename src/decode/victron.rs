@@ -0,0 +1,287 @@
+use bluer::Address;
+use serde::Serialize;
+
+use crate::config::Key;
+
+use super::crypto;
+use super::SensorReading;
+
+/// Victron Energy's Bluetooth SIG company identifier.
+pub const COMPANY_ID: u16 = 0x02e1;
+
+const PRODUCT_ADVERTISEMENT: u8 = 0x10;
+const HEADER_LEN: usize = 8;
+
+const SOLAR_CHARGER: u8 = 0x01;
+const BATTERY_MONITOR: u8 = 0x02;
+const DC_DC_CONVERTER: u8 = 0x04;
+
+const AUX_STARTER_VOLTAGE: u64 = 0;
+const AUX_MIDPOINT_VOLTAGE: u64 = 1;
+const AUX_TEMPERATURE: u64 = 2;
+
+/// Names of the device states shared by chargers and converters.
+const STATES: &[(u64, &str)] = &[
+    (0, "off"),
+    (1, "low_power"),
+    (2, "fault"),
+    (3, "bulk"),
+    (4, "absorption"),
+    (5, "float"),
+    (6, "storage"),
+    (7, "equalize"),
+    (9, "inverting"),
+    (11, "power_supply"),
+    (245, "starting_up"),
+    (246, "repeated_absorption"),
+    (247, "recondition"),
+    (248, "battery_safe"),
+    (252, "external_control"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    SolarCharger,
+    BatteryMonitor,
+    DcDcConverter,
+    Other(u8),
+}
+
+/// An Instant Readout advertisement of a Victron device.
+#[derive(Debug, Clone, Serialize)]
+pub struct Victron {
+    pub model_id: u16,
+    pub record: Record,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<&'static str>,
+    pub readings: Vec<SensorReading>,
+}
+
+impl Victron {
+    /// Parse the manufacturer data of company [`COMPANY_ID`], decrypting it with `key`.
+    pub fn parse(data: &[u8], address: Address, key: Option<&Key>) -> Option<Self> {
+        if data.len() <= HEADER_LEN || data[0] != PRODUCT_ADVERTISEMENT {
+            return None;
+        }
+        let mut victron = Self {
+            model_id: u16::from_le_bytes([data[2], data[3]]),
+            record: match data[4] {
+                SOLAR_CHARGER => Record::SolarCharger,
+                BATTERY_MONITOR => Record::BatteryMonitor,
+                DC_DC_CONVERTER => Record::DcDcConverter,
+                other => Record::Other(other),
+            },
+            state: None,
+            readings: vec![],
+        };
+        let key = match key {
+            Some(key) => key,
+            None => return Some(victron),
+        };
+        // the first byte of the key is sent to tell a wrong key from corrupt data
+        if data[7] != key.0[0] {
+            log::debug!("{}: Victron advertisement key does not match", address);
+            return Some(victron);
+        }
+
+        // the counter starts at the little endian nonce, records fit a single block
+        let mut iv = [0; 16];
+        iv[..2].copy_from_slice(&data[5..7]);
+        let ciphertext = &data[HEADER_LEN..data.len().min(HEADER_LEN + 16)];
+        let plaintext = match crypto::decrypt_ctr(key, &iv, ciphertext) {
+            Ok(plaintext) => plaintext,
+            Err(err) => {
+                log::debug!(
+                    "{}: failed to decrypt Victron advertisement: {}",
+                    address,
+                    err
+                );
+                return Some(victron);
+            }
+        };
+        let mut block = [0; 16];
+        block[..plaintext.len()].copy_from_slice(&plaintext);
+        let bits = Bits(u128::from_le_bytes(block));
+
+        match victron.record {
+            Record::SolarCharger => victron.solar_charger(&bits),
+            Record::BatteryMonitor => victron.battery_monitor(&bits),
+            Record::DcDcConverter => victron.dc_dc_converter(&bits),
+            Record::Other(record) => log::trace!("unsupported Victron record {:#04x}", record),
+        }
+        Some(victron)
+    }
+
+    fn solar_charger(&mut self, bits: &Bits) {
+        self.charger(bits);
+        self.push(
+            "voltage",
+            "volts",
+            bits.signed(16, 16).map(|v| v as f64 / 100.0),
+        );
+        self.push(
+            "current",
+            "amperes",
+            bits.signed(32, 16).map(|a| a as f64 / 10.0),
+        );
+        let kwh = bits.unsigned(48, 16).map(|kwh| kwh as f64 / 100.0);
+        self.push("yield_today", "kilowatt_hours", kwh);
+        self.push("power", "watts", bits.unsigned(64, 16).map(|w| w as f64));
+        let load = bits.unsigned(80, 9).map(|a| a as f64 / 10.0);
+        self.push("load_current", "amperes", load);
+    }
+
+    fn battery_monitor(&mut self, bits: &Bits) {
+        let minutes = bits.unsigned(0, 16).map(|minutes| minutes as f64);
+        self.push("remaining_time", "minutes", minutes);
+        self.push(
+            "voltage",
+            "volts",
+            bits.signed(16, 16).map(|v| v as f64 / 100.0),
+        );
+        self.push("alarm", "", bits.unsigned(32, 16).map(|alarm| alarm as f64));
+        match bits.unsigned(64, 2) {
+            Some(AUX_STARTER_VOLTAGE) => {
+                let volts = bits.signed(48, 16).map(|v| v as f64 / 100.0);
+                self.push("starter_voltage", "volts", volts);
+            }
+            Some(AUX_MIDPOINT_VOLTAGE) => {
+                let volts = bits.unsigned(48, 16).map(|v| v as f64 / 100.0);
+                self.push("midpoint_voltage", "volts", volts);
+            }
+            Some(AUX_TEMPERATURE) => {
+                let celsius = bits.unsigned(48, 16).map(|k| k as f64 / 100.0 - 273.15);
+                self.push("temperature", "celsius", celsius);
+            }
+            _ => {}
+        }
+        let amperes = bits.signed(66, 22).map(|a| a as f64 / 1000.0);
+        self.push("current", "amperes", amperes);
+        let consumed = bits.unsigned(88, 20).map(|ah| -(ah as f64) / 10.0);
+        self.push("consumed_charge", "amp_hours", consumed);
+        self.push(
+            "battery",
+            "percent",
+            bits.unsigned(108, 10).map(|soc| soc as f64 / 10.0),
+        );
+    }
+
+    fn dc_dc_converter(&mut self, bits: &Bits) {
+        self.charger(bits);
+        let input = bits.unsigned(16, 16).map(|v| v as f64 / 100.0);
+        self.push("voltage", "volts", input);
+        let output = bits.signed(32, 16).map(|v| v as f64 / 100.0);
+        self.push("voltage", "volts", output);
+        self.push(
+            "off_reason",
+            "",
+            bits.unsigned(48, 32).map(|reason| reason as f64),
+        );
+    }
+
+    /// The device state and error code, which start every charger record.
+    fn charger(&mut self, bits: &Bits) {
+        let state = bits.unsigned(0, 8);
+        self.state = state.and_then(|state| {
+            STATES
+                .iter()
+                .find(|(id, _)| *id == state)
+                .map(|(_, name)| *name)
+        });
+        self.push("charger_state", "", state.map(|state| state as f64));
+        self.push(
+            "charger_error",
+            "",
+            bits.unsigned(8, 8).map(|error| error as f64),
+        );
+    }
+
+    /// Add a reading unless the device marked it as not available.
+    fn push(&mut self, kind: &'static str, unit: &'static str, value: Option<f64>) {
+        if let Some(value) = value {
+            let index = self.readings.iter().filter(|r| r.kind == kind).count();
+            self.readings.push(SensorReading {
+                kind,
+                unit,
+                index,
+                value,
+            });
+        }
+    }
+}
+
+/// Fields packed least significant bit first, the largest value means not available.
+struct Bits(u128);
+
+impl Bits {
+    fn unsigned(&self, offset: u32, len: u32) -> Option<u64> {
+        let max = (1u128 << len) - 1;
+        let value = (self.0 >> offset) & max;
+        (value != max).then_some(value as u64)
+    }
+
+    fn signed(&self, offset: u32, len: u32) -> Option<i64> {
+        let value = ((self.0 >> offset) & ((1u128 << len) - 1)) as i64;
+        let value = value << (64 - len) >> (64 - len);
+        (value != (1 << (len - 1)) - 1).then_some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A SmartShunt battery monitor advertisement from the tests of the
+    /// victron-ble library, 12.53 V at 50 % with 50 Ah consumed.
+    const KEY: &str = "aff4d0995b7d1e176c0c33ecb9e70dcd";
+    const DATA: [u8; 23] = [
+        0x10, 0x02, // product advertisement
+        0x89, 0xa3, // model id
+        0x02, // battery monitor record
+        0xb0, 0x40, // nonce
+        0xaf, // first byte of the key
+        0x92, 0x5d, 0x09, 0xa4, 0xd8, 0x9a, 0xa0, 0x12, 0x8b, 0xde, 0xf4, 0x8c, 0x62, 0x98, 0xa9,
+    ];
+    const ADDRESS: Address = Address([0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0x01]);
+
+    fn reading(victron: &Victron, kind: &str) -> Option<f64> {
+        victron
+            .readings
+            .iter()
+            .find(|reading| reading.kind == kind)
+            .map(|reading| reading.value)
+    }
+
+    #[test]
+    fn decodes_battery_monitor() {
+        let key: Key = KEY.parse().unwrap();
+        let victron = Victron::parse(&DATA, ADDRESS, Some(&key)).unwrap();
+        assert_eq!(victron.model_id, 0xa389);
+        assert!(matches!(victron.record, Record::BatteryMonitor));
+        assert_eq!(reading(&victron, "voltage"), Some(12.53));
+        assert_eq!(reading(&victron, "battery"), Some(50.0));
+        assert_eq!(reading(&victron, "consumed_charge"), Some(-50.0));
+        assert_eq!(reading(&victron, "current"), Some(0.0));
+        assert_eq!(reading(&victron, "alarm"), Some(0.0));
+    }
+
+    #[test]
+    fn skips_other_keys() {
+        let mut key: Key = KEY.parse().unwrap();
+        key.0[0] ^= 1;
+        let victron = Victron::parse(&DATA, ADDRESS, Some(&key)).unwrap();
+        assert!(victron.readings.is_empty());
+    }
+
+    #[test]
+    fn reads_fields_lsb_first() {
+        let bits = Bits(0xfff_8000_7fff_1234);
+        assert_eq!(bits.unsigned(0, 16), Some(0x1234));
+        assert_eq!(bits.unsigned(16, 16), Some(0x7fff));
+        // the largest value of a field means not available
+        assert_eq!(bits.signed(16, 16), None);
+        assert_eq!(bits.signed(32, 16), Some(-0x8000));
+        assert_eq!(bits.unsigned(48, 12), None);
+    }
+}
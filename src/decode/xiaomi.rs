@@ -0,0 +1,256 @@
+use bluer::{Address, Uuid, UuidExt};
+use serde::Serialize;
+
+use crate::config::Key;
+
+use super::crypto;
+use super::SensorReading;
+
+/// Service data UUID of the ATC and pvvx custom firmware formats.
+pub const CUSTOM_SERVICE_UUID: u16 = 0x181a;
+/// Service data UUID of MiBeacon advertisements.
+pub const MIBEACON_SERVICE_UUID: u16 = 0xfe95;
+
+const ATC_LEN: usize = 13;
+const PVVX_LEN: usize = 15;
+
+const FRAME_ENCRYPTED: u16 = 0x0008;
+const FRAME_MAC: u16 = 0x0010;
+const FRAME_CAPABILITY: u16 = 0x0020;
+const FRAME_OBJECTS: u16 = 0x0040;
+const CAPABILITY_IO: u8 = 0x20;
+/// Encryption of MiBeacon v4 and later, older versions use a different scheme.
+const MIN_ENCRYPTED_VERSION: u16 = 4;
+/// Associated data of encrypted MiBeacon objects.
+const AAD: &[u8] = &[0x11];
+
+pub fn custom_service_uuid() -> Uuid {
+    Uuid::from_u16(CUSTOM_SERVICE_UUID)
+}
+
+pub fn mibeacon_service_uuid() -> Uuid {
+    Uuid::from_u16(MIBEACON_SERVICE_UUID)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Atc,
+    Pvvx,
+    MiBeacon,
+}
+
+/// Readings of a Xiaomi thermometer.
+#[derive(Debug, Clone, Serialize)]
+pub struct Xiaomi {
+    pub format: Format,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<u16>,
+    pub frame_counter: u8,
+    pub encrypted: bool,
+    pub readings: Vec<SensorReading>,
+}
+
+impl Xiaomi {
+    /// Parse the service data of UUID [`CUSTOM_SERVICE_UUID`].
+    pub fn parse_custom(data: &[u8]) -> Option<Self> {
+        let (format, temperature, humidity, battery, millivolts, frame_counter) = match data.len() {
+            ATC_LEN => (
+                Format::Atc,
+                i16::from_be_bytes([data[6], data[7]]) as f64 / 10.0,
+                data[8] as f64,
+                data[9],
+                u16::from_be_bytes([data[10], data[11]]),
+                data[12],
+            ),
+            PVVX_LEN => (
+                Format::Pvvx,
+                i16::from_le_bytes([data[6], data[7]]) as f64 / 100.0,
+                u16::from_le_bytes([data[8], data[9]]) as f64 / 100.0,
+                data[12],
+                u16::from_le_bytes([data[10], data[11]]),
+                data[13],
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            format,
+            product_id: None,
+            frame_counter,
+            encrypted: false,
+            readings: vec![
+                SensorReading::new("temperature", "celsius", temperature),
+                SensorReading::new("humidity", "percent", humidity),
+                SensorReading::new("battery", "percent", battery.into()),
+                SensorReading::new("voltage", "volts", millivolts as f64 / 1000.0),
+            ],
+        })
+    }
+
+    /// Parse the service data of UUID [`MIBEACON_SERVICE_UUID`], decrypting it with `key` if needed.
+    pub fn parse_mibeacon(data: &[u8], address: Address, key: Option<&Key>) -> Option<Self> {
+        if data.len() < 5 {
+            return None;
+        }
+        let frame_control = u16::from_le_bytes([data[0], data[1]]);
+        let product_id = u16::from_le_bytes([data[2], data[3]]);
+        let frame_counter = data[4];
+        let encrypted = frame_control & FRAME_ENCRYPTED != 0;
+        let mut xiaomi = Self {
+            format: Format::MiBeacon,
+            product_id: Some(product_id),
+            frame_counter,
+            encrypted,
+            readings: vec![],
+        };
+
+        let mut i = 5;
+        // the address is sent in little endian order
+        let mut mac: [u8; 6] = address.0;
+        mac.reverse();
+        if frame_control & FRAME_MAC != 0 {
+            mac = data.get(i..i + 6)?.try_into().unwrap();
+            i += 6;
+        }
+        if frame_control & FRAME_CAPABILITY != 0 {
+            let capability = *data.get(i)?;
+            i += if capability & CAPABILITY_IO != 0 {
+                3
+            } else {
+                1
+            };
+        }
+        if frame_control & FRAME_OBJECTS == 0 || i >= data.len() {
+            return Some(xiaomi);
+        }
+
+        let objects = if encrypted {
+            if frame_control >> 12 < MIN_ENCRYPTED_VERSION {
+                return Some(xiaomi);
+            }
+            match key {
+                Some(key) => decrypt(data, i, mac, address, key)?,
+                None => return Some(xiaomi),
+            }
+        } else {
+            data[i..].to_vec()
+        };
+
+        let mut objects = objects.as_slice();
+        while objects.len() >= 3 {
+            let id = u16::from_le_bytes([objects[0], objects[1]]);
+            let len = objects[2] as usize;
+            let value = match objects.get(3..3 + len) {
+                Some(value) => value,
+                None => break,
+            };
+            xiaomi.object(id, value);
+            objects = &objects[3 + len..];
+        }
+        Some(xiaomi)
+    }
+
+    fn object(&mut self, id: u16, value: &[u8]) {
+        let i16_at = |i: usize| i16::from_le_bytes([value[i], value[i + 1]]) as f64;
+        let u16_at = |i: usize| u16::from_le_bytes([value[i], value[i + 1]]) as f64;
+        match (id, value.len()) {
+            (0x1004, 2) => self.push("temperature", "celsius", i16_at(0) / 10.0),
+            (0x1006, 2) => self.push("humidity", "percent", u16_at(0) / 10.0),
+            (0x100a, 1..) => self.push("battery", "percent", value[0].into()),
+            (0x100d, 4) => {
+                self.push("temperature", "celsius", i16_at(0) / 10.0);
+                self.push("humidity", "percent", u16_at(2) / 10.0);
+            }
+            (0x4c01, 4) => {
+                let celsius = f32::from_le_bytes(value.try_into().unwrap());
+                self.push("temperature", "celsius", celsius.into());
+            }
+            (0x4c02, 1) => self.push("humidity", "percent", value[0].into()),
+            (0x4c03, 1) => self.push("battery", "percent", value[0].into()),
+            _ => log::trace!("unsupported MiBeacon object {:#06x}", id),
+        }
+    }
+
+    fn push(&mut self, kind: &'static str, unit: &'static str, value: f64) {
+        self.readings.push(SensorReading::new(kind, unit, value));
+    }
+}
+
+/// Decrypt the objects starting at `start`, followed by a 3 byte extended
+/// frame counter and a 4 byte message integrity check.
+fn decrypt(
+    data: &[u8],
+    start: usize,
+    mac: [u8; 6],
+    address: Address,
+    key: &Key,
+) -> Option<Vec<u8>> {
+    if data.len() < start + 7 {
+        return None;
+    }
+    let (ciphertext, trailer) = data[start..].split_at(data.len() - start - 7);
+    let (counter, mic) = trailer.split_at(3);
+
+    let mut nonce = mac.to_vec();
+    nonce.extend_from_slice(&data[2..5]);
+    nonce.extend_from_slice(counter);
+
+    match crypto::decrypt_ccm(key, &nonce, AAD, ciphertext, mic) {
+        Ok(objects) => Some(objects),
+        Err(err) => {
+            log::debug!(
+                "{}: failed to decrypt MiBeacon advertisement: {}",
+                address,
+                err
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An encrypted LYWSD03MMC advertisement of 23.0 °C and 50.1 %, encrypted
+    /// with the AES-CCM implementation of the Python cryptography package.
+    const BINDKEY: &str = "814aac74c4f17b6c1581e1ab87816b99";
+    const ADDRESS: Address = Address([0xa4, 0xc1, 0x38, 0xaa, 0xbb, 0xcc]);
+    const DATA: [u8; 25] = [
+        0x58, 0x58, // frame control: v5, objects, MAC, encrypted
+        0x5b, 0x05, // product id
+        0x32, // frame counter
+        0xcc, 0xbb, 0xaa, 0x38, 0xc1, 0xa4, // MAC
+        0x13, 0x5b, 0x4a, 0xae, 0x6a, 0xd6, 0xdf, // temperature and humidity object
+        0x11, 0x00, 0x00, // extended frame counter
+        0x17, 0x7b, 0xc3, 0xc0, // message integrity check
+    ];
+
+    #[test]
+    fn decrypts_mibeacon() {
+        let key: Key = BINDKEY.parse().unwrap();
+        let xiaomi = Xiaomi::parse_mibeacon(&DATA, ADDRESS, Some(&key)).unwrap();
+        assert!(xiaomi.encrypted);
+        assert_eq!(xiaomi.product_id, Some(0x055b));
+        assert_eq!(xiaomi.frame_counter, 0x32);
+        let readings: Vec<_> = xiaomi
+            .readings
+            .iter()
+            .map(|reading| (reading.kind, reading.unit, reading.value))
+            .collect();
+        assert_eq!(
+            readings,
+            [
+                ("temperature", "celsius", 23.0),
+                ("humidity", "percent", 50.1)
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_bindkey() {
+        let mut key: Key = BINDKEY.parse().unwrap();
+        key.0[15] ^= 1;
+        assert!(Xiaomi::parse_mibeacon(&DATA, ADDRESS, Some(&key)).is_none());
+    }
+}
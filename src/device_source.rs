@@ -0,0 +1,80 @@
+use futures_util::stream::BoxStream;
+#[cfg(test)]
+use futures_util::StreamExt;
+
+use crate::bluetooth::{self, DeviceEvent};
+use crate::btsnoop;
+use crate::config;
+use crate::jsonl;
+
+pub trait DeviceSource {
+    fn events(&self) -> BoxStream<'static, DeviceEvent>;
+}
+
+/// Device source emitting a fixed list of events, for tests.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub struct Memory {
+    events: Vec<DeviceEvent>,
+}
+
+#[cfg(test)]
+impl Memory {
+    pub fn new(events: Vec<DeviceEvent>) -> Self {
+        Self { events }
+    }
+}
+
+#[cfg(test)]
+impl DeviceSource for Memory {
+    fn events(&self) -> BoxStream<'static, DeviceEvent> {
+        futures_util::stream::iter(self.events.clone()).boxed()
+    }
+}
+
+#[derive(Clone)]
+pub enum DeviceSources {
+    Bluez(bluetooth::Bluez),
+    Replay(jsonl::Replay),
+    Btsnoop(btsnoop::Capture),
+}
+
+impl DeviceSources {
+    pub fn bluez(config: config::Bluetooth) -> Self {
+        Self::Bluez(bluetooth::Bluez::new(config))
+    }
+
+    pub fn replay(config: config::Replay) -> Self {
+        Self::Replay(jsonl::Replay::new(config))
+    }
+
+    pub fn btsnoop(config: config::Btsnoop) -> Self {
+        Self::Btsnoop(btsnoop::Capture::new(config))
+    }
+
+    pub fn events(&self) -> BoxStream<'static, DeviceEvent> {
+        match self {
+            Self::Bluez(source) => source.events(),
+            Self::Replay(source) => source.events(),
+            Self::Btsnoop(source) => source.events(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluetooth::Device;
+
+    #[tokio::test]
+    async fn memory_emits_its_events_in_order() {
+        let device = Device::default();
+        let source = Memory::new(vec![
+            DeviceEvent::Added(device.clone()),
+            DeviceEvent::Updated(device.clone()),
+            DeviceEvent::Removed(device),
+        ]);
+        let names: Vec<_> = source.events().map(|event| event.name()).collect().await;
+        assert_eq!(names, ["added", "updated", "removed"]);
+    }
+}
@@ -1,12 +1,13 @@
 use async_trait::async_trait;
 
-use crate::bluetooth::Device;
+use crate::bluetooth::DeviceEvent;
 use crate::config;
-use crate::{loki, prometheus};
+use crate::decode::Decoded;
+use crate::{jsonl, loki, prometheus};
 
 #[async_trait]
 pub trait DeviceWriter {
-    async fn write(&mut self, device: Device);
+    async fn write(&mut self, event: DeviceEvent, decoded: &Decoded);
 }
 
 #[derive(Clone)]
@@ -18,6 +19,7 @@ where
     PrometheusExporter(prometheus::Exporter),
     PrometheusRemoteWrite(prometheus::RemoteWrite<PC>),
     Loki(loki::Push<LC>),
+    Record(jsonl::Recorder),
 }
 
 impl<PC, LC> DeviceWriters<PC, LC>
@@ -37,11 +39,16 @@ where
         Self::Loki(loki::Push::new(client))
     }
 
-    pub async fn write(&mut self, device: Device) {
+    pub fn record(recorder: jsonl::Recorder) -> Self {
+        Self::Record(recorder)
+    }
+
+    pub async fn write(&mut self, event: DeviceEvent, decoded: &Decoded) {
         match self {
-            Self::PrometheusRemoteWrite(writer) => writer.write(device).await,
-            Self::Loki(writer) => writer.write(device).await,
-            Self::PrometheusExporter(writer) => writer.write(device).await,
+            Self::PrometheusRemoteWrite(writer) => writer.write(event, decoded).await,
+            Self::Loki(writer) => writer.write(event, decoded).await,
+            Self::PrometheusExporter(writer) => writer.write(event, decoded).await,
+            Self::Record(writer) => writer.write(event, decoded).await,
         }
     }
 }
@@ -0,0 +1,5 @@
+mod record;
+mod replay;
+
+pub use record::Recorder;
+pub use replay::Replay;
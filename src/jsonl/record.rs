@@ -0,0 +1,115 @@
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::bluetooth::{Device, DeviceEvent};
+use crate::config;
+use crate::decode::Decoded;
+use crate::device_writer;
+
+/// Appends every device event to a JSON-lines file.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn new(config: config::Record) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config.path)?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(File::from_std(file))),
+        })
+    }
+}
+
+#[async_trait]
+impl device_writer::DeviceWriter for Recorder {
+    async fn write(&mut self, event: DeviceEvent, _decoded: &Decoded) {
+        let line = Line {
+            event: event.name(),
+            device: event.device(),
+        };
+        let mut line = match serde_json::to_string(&line) {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("failed to serialize device: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        let mut file = self.file.lock().await;
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            log::error!("failed to record device: {}", e);
+            return;
+        }
+        if let Err(e) = file.flush().await {
+            log::error!("failed to flush recording: {}", e);
+        }
+    }
+}
+
+/// A recorded event, the device with the name of the event.
+#[derive(Serialize)]
+struct Line<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    device: &'a Device,
+}
+
+#[cfg(test)]
+mod tests {
+    use bluer::Address;
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::device_source::{DeviceSource, Memory};
+    use crate::device_writer::DeviceWriter;
+    use crate::jsonl::Replay;
+
+    #[tokio::test]
+    async fn replays_recorded_events() {
+        let device = Device {
+            address: Address([1, 2, 3, 4, 5, 6]),
+            ..Default::default()
+        };
+        let recorded = vec![
+            DeviceEvent::Added(device.clone()),
+            DeviceEvent::Updated(device.clone()),
+            DeviceEvent::Lost(device.clone()),
+            DeviceEvent::Added(device.clone()),
+            DeviceEvent::Removed(device),
+        ];
+
+        let path = std::env::temp_dir().join(format!("recording-{}.jsonl", std::process::id()));
+        let mut recorder = Recorder::new(config::Record { path: path.clone() }).unwrap();
+        let mut events = Memory::new(recorded.clone()).events();
+        while let Some(event) = events.next().await {
+            let decoded = Decoded::from(event.device());
+            recorder.write(event, &decoded).await;
+        }
+
+        let replay = Replay::new(config::Replay {
+            path: path.clone(),
+            speed: Some(0.0),
+        });
+        let replayed: Vec<_> = replay.events().collect().await;
+        std::fs::remove_file(path).unwrap();
+
+        let names = |events: &[DeviceEvent]| -> Vec<_> {
+            events
+                .iter()
+                .map(|event| (event.name(), event.device().address))
+                .collect()
+        };
+        assert_eq!(names(&replayed), names(&recorded));
+    }
+}
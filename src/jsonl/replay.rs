@@ -0,0 +1,107 @@
+use std::time::SystemTime;
+
+use async_stream::stream;
+use futures_util::stream::{BoxStream, StreamExt};
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::bluetooth::{Device, DeviceEvent};
+use crate::config;
+use crate::device_source;
+
+/// Replays devices from a JSON-lines file written by the [`Recorder`](super::Recorder).
+#[derive(Clone, Debug)]
+pub struct Replay {
+    config: config::Replay,
+}
+
+impl Replay {
+    pub fn new(config: config::Replay) -> Self {
+        Self { config }
+    }
+}
+
+impl device_source::DeviceSource for Replay {
+    fn events(&self) -> BoxStream<'static, DeviceEvent> {
+        let path = self.config.path.clone();
+        let speed = self.config.speed.unwrap_or(1.0);
+
+        stream! {
+            let file = match File::open(&path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    log::error!("failed to open recording {}: {}", path.display(), e);
+                    return;
+                }
+            };
+
+            let mut lines = BufReader::new(file).lines();
+            let mut previous: Option<SystemTime> = None;
+            let mut number = 0;
+
+            loop {
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::error!("failed to read recording {}: {}", path.display(), e);
+                        break;
+                    }
+                };
+                number += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let recorded = match serde_json::from_str::<Line>(&line) {
+                    Ok(line) => line.event,
+                    Err(e) => {
+                        log::warn!("skipping line {} of {}: {}", number, path.display(), e);
+                        continue;
+                    }
+                };
+                let device: Device = match serde_json::from_str(&line) {
+                    Ok(device) => device,
+                    Err(e) => {
+                        log::warn!("skipping line {} of {}: {}", number, path.display(), e);
+                        continue;
+                    }
+                };
+
+                if speed > 0.0 {
+                    let delay = previous.and_then(|previous| device.timestamp.duration_since(previous).ok());
+                    if let Some(delay) = delay {
+                        tokio::time::sleep(delay.div_f64(speed)).await;
+                    }
+                }
+                previous = Some(device.timestamp);
+
+                yield match recorded {
+                    Event::Added => DeviceEvent::Added(device),
+                    Event::Updated => DeviceEvent::Updated(device),
+                    Event::Lost => DeviceEvent::Lost(device),
+                    Event::Removed => DeviceEvent::Removed(device),
+                };
+            }
+
+            log::info!("Finished replaying {}", path.display());
+        }
+        .boxed()
+    }
+}
+
+/// The event of a recorded line, the device is read separately.
+#[derive(Deserialize)]
+struct Line {
+    event: Event,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Event {
+    Added,
+    Updated,
+    Lost,
+    Removed,
+}
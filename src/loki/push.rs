@@ -2,8 +2,11 @@ use std::ops::{Deref, DerefMut};
 
 use async_trait::async_trait;
 
-use crate::bluetooth::Device;
+use serde::Serialize;
+
+use crate::bluetooth::{Device, DeviceEvent};
 use crate::config::HOSTNAME;
+use crate::decode::{self, Decoded, Reading};
 use crate::device_writer;
 
 use super::Client;
@@ -31,13 +34,23 @@ impl<C> device_writer::DeviceWriter for Push<C>
 where
     C: Client + Send + Sync,
 {
-    async fn write(&mut self, device: Device) {
+    async fn write(&mut self, event: DeviceEvent, decoded: &Decoded) {
+        let device = event.device();
+        if decode::aggregated(device) {
+            return;
+        }
+        let line = Line {
+            event: event.name(),
+            device,
+            readings: decoded.readings(device),
+            decoded,
+        };
         let req = PushRequest {
             streams: vec![StreamAdapter {
-                labels: Labels::from(device.clone()).0,
+                labels: Labels::from(&line).0,
                 entries: vec![EntryAdapter {
                     timestamp: Some(prost_types::Timestamp::from(device.timestamp)),
-                    line: serde_json::to_string(&device).unwrap(),
+                    line: serde_json::to_string(&line).unwrap(),
                 }],
                 hash: 0,
             }],
@@ -46,17 +59,35 @@ where
     }
 }
 
+#[derive(Serialize)]
+struct Line<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    device: &'a Device,
+    decoded: &'a Decoded,
+    readings: Vec<Reading>,
+}
+
 pub struct Labels(pub String);
 
-impl From<Device> for Labels {
-    fn from(device: Device) -> Self {
+impl From<&Line<'_>> for Labels {
+    fn from(line: &Line) -> Self {
+        let device = line.device;
         #[allow(clippy::unnecessary_to_owned)]
         let host = HOSTNAME.to_string();
 
-        let mut labels = format!("address={:?}, host={:?}", device.address.to_string(), host);
-        if let Some(name) = device.name {
+        let mut labels = format!(
+            "adapter={:?}, address={:?}, host={:?}",
+            device.adapter,
+            device.address.to_string(),
+            host
+        );
+        if let Some(name) = &device.name {
             labels = format!("{}, name={:?}", labels, name);
         }
+        for (name, value) in line.decoded.labels() {
+            labels = format!("{}, {}={:?}", labels, name, value);
+        }
         labels = format!("{{{}}}", labels);
 
         log::trace!("loki labels: {}", labels);
@@ -1,12 +1,21 @@
-use futures_util::{pin_mut, stream::StreamExt};
+use std::sync::Arc;
+
+use futures_util::stream::StreamExt;
+use tokio::sync::mpsc;
 
 mod bluetooth;
+mod btsnoop;
 mod config;
+mod decode;
+mod device_source;
 mod device_writer;
+mod jsonl;
 mod loki;
 mod prometheus;
 
-use crate::bluetooth::discover;
+use crate::bluetooth::DeviceEvent;
+use crate::decode::Decoded;
+use crate::device_source::DeviceSources;
 use crate::device_writer::DeviceWriters;
 
 #[tokio::main]
@@ -37,29 +46,69 @@ async fn main() {
         writers.push(device_writer::DeviceWriters::loki(client));
     }
 
+    if let Some(record_config) = config::CONFIG.record.clone() {
+        log::info!("Recording devices to {}", record_config.path.display());
+        match jsonl::Recorder::new(record_config) {
+            Ok(recorder) => writers.push(device_writer::DeviceWriters::record(recorder)),
+            Err(e) => log::error!("Failed to open recording: {}", e),
+        }
+    }
+
     if writers.is_empty() {
         log::error!("No writers enabled");
         return;
     }
 
-    let devices = discover();
-    pin_mut!(devices);
+    let source = if let Some(replay_config) = config::CONFIG.replay.clone() {
+        log::info!("Replaying devices from {}", replay_config.path.display());
+        DeviceSources::replay(replay_config)
+    } else if let Some(btsnoop_config) = config::CONFIG.btsnoop.clone() {
+        log::info!("Reading btsnoop capture {}", btsnoop_config.path.display());
+        DeviceSources::btsnoop(btsnoop_config)
+    } else {
+        DeviceSources::bluez(config::CONFIG.bluetooth.clone())
+    };
+    let exporting = writers
+        .iter()
+        .any(|writer| matches!(writer, DeviceWriters::PrometheusExporter(_)));
+
+    // every writer gets events in order from its own task
+    let (senders, tasks): (Vec<_>, Vec<_>) = writers
+        .into_iter()
+        .map(|mut writer| {
+            let (sender, mut receiver) = mpsc::unbounded_channel::<(DeviceEvent, Arc<Decoded>)>();
+            let task = tokio::spawn(async move {
+                while let Some((event, decoded)) = receiver.recv().await {
+                    writer.write(event.clone(), &decoded).await;
+                    log::trace!("Wrote device event: {:?}", event);
+                }
+            });
+            (sender, task)
+        })
+        .unzip();
+
+    let mut events = source.events();
+    while let Some(event) = events.next().await {
+        if decode::dropped(event.device()) {
+            continue;
+        }
+        // decoded once for all writers
+        let decoded = Arc::new(Decoded::from(event.device()));
+        for sender in &senders {
+            let _ = sender.send((event.clone(), decoded.clone()));
+        }
+    }
+
+    // let queued writes finish once a finite source, such as a replay, ends
+    drop(senders);
+    for task in tasks {
+        let _ = task.await;
+    }
 
-    loop {
-        let device = devices.next().await;
-        match device {
-            Some(Ok(device)) => {
-                writers.iter().for_each(|writer| {
-                    let device = device.clone();
-                    let mut writer = writer.clone();
-                    tokio::spawn(async move {
-                        writer.write(device.clone()).await;
-                        log::trace!("Wrote device: {:?}", device);
-                    });
-                });
-            }
-            Some(Err(e)) => log::error!("Discovery error {:?}", e),
-            None => log::trace!("no devices found"),
+    if exporting {
+        log::info!("No more devices, serving the last metrics until interrupted");
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to wait for interrupt: {}", e);
         }
     }
 }
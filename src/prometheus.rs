@@ -1,5 +1,7 @@
 mod client;
+mod devices;
 mod exporter;
+#[allow(dead_code)]
 mod proto;
 mod remote_write;
 
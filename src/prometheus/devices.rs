@@ -0,0 +1,144 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bluer::Address;
+
+use crate::bluetooth::DeviceEvent;
+use crate::decode::Decoded;
+
+/// Devices that are present, for metrics aggregated over all of them.
+///
+/// Writers get events at their own pace, so each one tracks its own.
+#[derive(Debug, Clone, Default)]
+pub struct Present {
+    devices: HashMap<(String, Address), Tracked>,
+    counts: Counts,
+}
+
+/// What a present device was counted as.
+#[derive(Debug, Clone)]
+struct Tracked {
+    manufacturer: Option<String>,
+    address_type: &'static str,
+    device_family: Option<&'static str>,
+    apple_messages: BTreeSet<String>,
+}
+
+/// Present devices by manufacturer, address type, device family and Apple Continuity message type.
+///
+/// Counts stay at zero once their devices are gone.
+#[derive(Debug, Clone, Default)]
+pub struct Counts {
+    pub by_manufacturer: BTreeMap<String, usize>,
+    pub by_address_type: BTreeMap<&'static str, usize>,
+    pub by_device_family: BTreeMap<&'static str, usize>,
+    pub by_apple_message: BTreeMap<String, usize>,
+}
+
+impl Present {
+    /// Track the device of `event` and count the present devices.
+    pub fn count(&mut self, event: &DeviceEvent, decoded: &Decoded) -> Counts {
+        let device = event.device();
+        let key = (device.adapter.clone(), device.address);
+
+        if let Some(tracked) = self.devices.remove(&key) {
+            let counts = &mut self.counts;
+            let manufacturer = tracked.manufacturer.as_ref();
+            if let Some(count) = manufacturer.and_then(|m| counts.by_manufacturer.get_mut(m)) {
+                *count -= 1;
+            }
+            if let Some(count) = counts.by_address_type.get_mut(tracked.address_type) {
+                *count -= 1;
+            }
+            let device_family = tracked.device_family;
+            if let Some(count) = device_family.and_then(|f| counts.by_device_family.get_mut(f)) {
+                *count -= 1;
+            }
+            for message in tracked.apple_messages {
+                if let Some(count) = counts.by_apple_message.get_mut(&message) {
+                    *count -= 1;
+                }
+            }
+        }
+        if !event.is_gone() {
+            let tracked = Tracked {
+                manufacturer: decoded.manufacturer(),
+                address_type: decoded.address_kind.as_str(),
+                device_family: decoded.device_family,
+                // devices often send several messages of the same type
+                apple_messages: decoded
+                    .apple
+                    .iter()
+                    .flat_map(|a| a.messages.clone())
+                    .collect(),
+            };
+            let counts = &mut self.counts;
+            if let Some(manufacturer) = &tracked.manufacturer {
+                *counts
+                    .by_manufacturer
+                    .entry(manufacturer.clone())
+                    .or_default() += 1;
+            }
+            *counts
+                .by_address_type
+                .entry(tracked.address_type)
+                .or_default() += 1;
+            if let Some(device_family) = tracked.device_family {
+                *counts.by_device_family.entry(device_family).or_default() += 1;
+            }
+            for message in &tracked.apple_messages {
+                *counts.by_apple_message.entry(message.clone()).or_default() += 1;
+            }
+            self.devices.insert(key, tracked);
+        }
+
+        self.counts.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::bluetooth::Device;
+    use crate::device_source::{DeviceSource, Memory};
+
+    fn device(address: [u8; 6], company: u16) -> Device {
+        Device {
+            adapter: "hci0".to_owned(),
+            address: Address(address),
+            manufacturer_data: [(company, vec![0])].into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_present_devices() {
+        let ruuvi = device([1, 0, 0, 0, 0, 1], 0x0499);
+        let source = Memory::new(vec![
+            DeviceEvent::Added(ruuvi.clone()),
+            DeviceEvent::Added(device([1, 0, 0, 0, 0, 2], 0x0499)),
+            // updates of a present device are not counted again
+            DeviceEvent::Updated(ruuvi.clone()),
+            DeviceEvent::Added(device([1, 0, 0, 0, 0, 3], 0x0006)),
+            DeviceEvent::Lost(ruuvi),
+        ]);
+
+        let mut present = Present::default();
+        let mut counts = Counts::default();
+        let mut events = source.events();
+        while let Some(event) = events.next().await {
+            counts = present.count(&event, &Decoded::from(event.device()));
+        }
+
+        let by_manufacturer: Vec<_> = counts.by_manufacturer.into_iter().collect();
+        assert_eq!(
+            by_manufacturer,
+            [
+                ("Microsoft".to_owned(), 1),
+                ("Ruuvi Innovations Ltd.".to_owned(), 1)
+            ]
+        );
+        assert_eq!(counts.by_address_type.get("public"), Some(&2));
+    }
+}
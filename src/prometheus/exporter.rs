@@ -1,7 +1,11 @@
+use std::collections::HashMap;
 use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use ::prometheus::{opts, register_gauge_vec, Encoder, GaugeVec, TextEncoder};
 use async_trait::async_trait;
+use bluer::Address;
 use hyper::{
     header::CONTENT_TYPE,
     service::{make_service_fn, service_fn},
@@ -9,27 +13,84 @@ use hyper::{
 };
 use lazy_static::lazy_static;
 
+use super::devices;
+
 use crate::config;
+use crate::decode::{self, Decoded, Reading};
 use crate::device_writer;
-use crate::{bluetooth::Device, config::HOSTNAME};
+use crate::{bluetooth::DeviceEvent, config::HOSTNAME};
+
+/// Labels of every metric, followed by the labels of the reading.
+const LABELS: [&str; 6] = [
+    "adapter",
+    "address",
+    "address_type",
+    "host",
+    "identity",
+    "name",
+];
 
 lazy_static! {
-    static ref RSSI: GaugeVec = register_gauge_vec!(
+    static ref DEVICES_BY_MANUFACTURER: GaugeVec = register_gauge_vec!(
+        opts!(
+            "bluetooth_devices_by_manufacturer",
+            "The number of present bluetooth devices by manufacturer.",
+        ),
+        &["host", "manufacturer"]
+    )
+    .unwrap();
+    static ref DEVICES_BY_ADDRESS_TYPE: GaugeVec = register_gauge_vec!(
+        opts!(
+            "bluetooth_devices_by_address_type",
+            "The number of present bluetooth devices by address type.",
+        ),
+        &["host", "address_type"]
+    )
+    .unwrap();
+    static ref DEVICES_BY_DEVICE_FAMILY: GaugeVec = register_gauge_vec!(
         opts!(
-            "bluetooth_rssi",
-            "The Received Signal Strength Indicator value for the bluetooth device.",
+            "bluetooth_devices_by_device_family",
+            "The number of present bluetooth devices by device family.",
         ),
-        &["address", "host", "name"]
+        &["host", "device_family"]
     )
     .unwrap();
-    static ref TX_POWER: GaugeVec = register_gauge_vec!(
+    static ref DEVICES_BY_APPLE_MESSAGE: GaugeVec = register_gauge_vec!(
         opts!(
-            "bluetooth_tx_power",
-            "The transmit power of the bluetooth device.",
+            "bluetooth_devices_by_apple_message",
+            "The number of present bluetooth devices sending an Apple Continuity message type.",
         ),
-        &["address", "host", "name"]
+        &["host", "message_type"]
     )
     .unwrap();
+    /// Gauges registered so far, by metric name.
+    static ref GAUGES: Mutex<HashMap<&'static str, GaugeVec>> = Mutex::new(HashMap::new());
+    /// Series set for each present device.
+    static ref SERIES: Mutex<HashMap<(String, Address), Series>> = Mutex::new(HashMap::new());
+}
+
+/// Label values of the series of a device, by metric name and the label values
+/// of the reading, which are empty for readings with a single series.
+type Series = HashMap<(&'static str, Vec<String>), Vec<String>>;
+
+/// Get the gauge of a reading, registering it the first time it is seen.
+fn gauge(reading: &Reading) -> prometheus::Result<GaugeVec> {
+    let mut gauges = GAUGES.lock().unwrap();
+    if let Some(gauge) = gauges.get(reading.name) {
+        return Ok(gauge.clone());
+    }
+    let mut labels = LABELS.to_vec();
+    labels.extend(reading.labels.keys());
+    let gauge = register_gauge_vec!(opts!(reading.name, reading.help), &labels)?;
+    gauges.insert(reading.name, gauge.clone());
+    Ok(gauge)
+}
+
+fn remove_series(name: &str, values: &[String]) {
+    if let Some(gauge) = GAUGES.lock().unwrap().get(name) {
+        let values: Vec<_> = values.iter().map(String::as_str).collect();
+        let _ = gauge.remove_label_values(&values);
+    }
 }
 
 async fn handle(_req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
@@ -53,14 +114,16 @@ async fn handle(_req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
 #[derive(Clone, Debug)]
 pub struct Exporter {
     config: config::PrometheusExporter,
-    listening: bool,
+    listening: Arc<AtomicBool>,
+    present: devices::Present,
 }
 
 impl Exporter {
     pub fn new(config: config::PrometheusExporter) -> Self {
         Self {
             config,
-            listening: false,
+            listening: Arc::new(AtomicBool::new(false)),
+            present: devices::Present::default(),
         }
     }
 
@@ -81,33 +144,196 @@ impl Exporter {
 
 #[async_trait]
 impl device_writer::DeviceWriter for Exporter {
-    async fn write(&mut self, device: Device) {
-        if !self.listening {
-            self.listening = true;
+    async fn write(&mut self, event: DeviceEvent, decoded: &Decoded) {
+        // the flag is shared between clones of the writer
+        if !self.listening.swap(true, Ordering::SeqCst) {
             self.run();
         }
+        export(&mut self.present, &event, decoded);
+    }
+}
+
+/// Update the metrics of the device of `event`, and the counts of the `present` devices.
+fn export(present: &mut devices::Present, event: &DeviceEvent, decoded: &Decoded) {
+    let device = event.device();
+    let address = device.address.to_string();
+    let host = HOSTNAME.to_string();
+    let name = device.name.clone().unwrap_or_default();
 
-        let host = HOSTNAME.to_string();
-        if let Some(rssi) = device.rssi {
-            RSSI.with_label_values(&[
-                device.address.to_string().as_str(),
-                host.as_str(),
-                device
-                    .name
-                    .clone()
-                    .unwrap_or_else(|| "".to_string())
-                    .as_str(),
-            ])
-            .set(rssi.into());
+    let counts = present.count(event, decoded);
+    for (manufacturer, count) in counts.by_manufacturer {
+        DEVICES_BY_MANUFACTURER
+            .with_label_values(&[&host, &manufacturer])
+            .set(count as f64);
+    }
+    for (address_type, count) in counts.by_address_type {
+        DEVICES_BY_ADDRESS_TYPE
+            .with_label_values(&[&host, address_type])
+            .set(count as f64);
+    }
+    for (device_family, count) in counts.by_device_family {
+        DEVICES_BY_DEVICE_FAMILY
+            .with_label_values(&[&host, device_family])
+            .set(count as f64);
+    }
+    for (message_type, count) in counts.by_apple_message {
+        DEVICES_BY_APPLE_MESSAGE
+            .with_label_values(&[&host, &message_type])
+            .set(count as f64);
+    }
+    if decode::aggregated(device) {
+        return;
+    }
+
+    let key = (device.adapter.clone(), device.address);
+    let mut series = SERIES.lock().unwrap();
+    if event.is_gone() {
+        for ((name, _), values) in series.remove(&key).unwrap_or_default() {
+            remove_series(name, &values);
         }
-        if let Some(tx_power) = device.tx_power {
-            TX_POWER
-                .with_label_values(&[
-                    device.address.to_string().as_str(),
-                    host.as_str(),
-                    device.name.unwrap_or_else(|| "".to_string()).as_str(),
-                ])
-                .set(tx_power.into());
+        return;
+    }
+
+    // readings missing from this event keep their series, devices such as
+    // Xiaomi sensors send a different reading in every advertisement
+    let series = series.entry(key).or_default();
+    for reading in decoded.readings(device) {
+        let gauge = match gauge(&reading) {
+            Ok(gauge) => gauge,
+            Err(err) => {
+                log::error!("failed to register {}: {}", reading.name, err);
+                continue;
+            }
+        };
+        let mut values = vec![
+            device.adapter.as_str(),
+            &address,
+            decoded.address_kind.as_str(),
+            &host,
+            decoded.identity.as_deref().unwrap_or_default(),
+            &name,
+        ];
+        values.extend(reading.labels.values().map(String::as_str));
+
+        let series_key = if reading.single {
+            vec![]
+        } else {
+            reading.labels.values().cloned().collect()
+        };
+        let owned = values.iter().map(|value| value.to_string()).collect();
+        match series.insert((reading.name, series_key), owned) {
+            Some(previous) if previous != values => remove_series(reading.name, &previous),
+            _ => {}
+        }
+        match gauge.get_metric_with_label_values(&values) {
+            Ok(metric) => metric.set(reading.value),
+            Err(err) => log::error!("failed to set {}: {}", reading.name, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::bluetooth::Device;
+
+    fn export_event(present: &mut devices::Present, event: DeviceEvent) {
+        export(present, &event, &Decoded::from(event.device()));
+    }
+
+    /// Label pairs of the series of `name` for the device at `address`.
+    fn series(name: &str, address: Address) -> Vec<BTreeMap<String, String>> {
+        let address = address.to_string();
+        ::prometheus::gather()
+            .iter()
+            .filter(|family| family.get_name() == name)
+            .flat_map(|family| family.get_metric())
+            .map(|metric| {
+                metric
+                    .get_label()
+                    .iter()
+                    .map(|pair| (pair.get_name().to_owned(), pair.get_value().to_owned()))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .filter(|labels| labels["address"] == address)
+            .collect()
+    }
+
+    fn ibeacon(address: Address, minor: u8) -> Device {
+        let mut data = vec![0x02, 0x15];
+        data.extend([0xaa; 16]);
+        data.extend([0x00, 0x01, 0x00, minor, 0xc5]);
+        Device {
+            address,
+            rssi: Some(-70),
+            manufacturer_data: [(0x004c, data)].into(),
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn replaces_changed_ibeacon_labels() {
+        let address = Address([0x00, 0x00, 0x00, 0x00, 0x01, 0x01]);
+        let mut present = devices::Present::default();
+        export_event(&mut present, DeviceEvent::Added(ibeacon(address, 1)));
+        export_event(&mut present, DeviceEvent::Updated(ibeacon(address, 2)));
+
+        let rssi = series("bluetooth_rssi", address);
+        assert_eq!(rssi.len(), 1);
+        assert_eq!(rssi[0]["ibeacon_minor"], "2");
+        let measured_power = series("bluetooth_ibeacon_measured_power", address);
+        assert_eq!(measured_power.len(), 1);
+        assert_eq!(measured_power[0]["ibeacon_minor"], "2");
+
+        export_event(&mut present, DeviceEvent::Lost(ibeacon(address, 2)));
+        assert!(series("bluetooth_rssi", address).is_empty());
+        assert!(series("bluetooth_ibeacon_measured_power", address).is_empty());
+    }
+
+    #[test]
+    fn replaces_changed_device_info() {
+        let address = Address([0x00, 0x00, 0x00, 0x00, 0x01, 0x02]);
+        let mut present = devices::Present::default();
+        let device = |appearance| Device {
+            address,
+            appearance: Some(appearance),
+            ..Default::default()
+        };
+        export_event(&mut present, DeviceEvent::Added(device(0x0040)));
+        export_event(&mut present, DeviceEvent::Updated(device(0x00c0)));
+
+        let info = series("bluetooth_device_info", address);
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0]["appearance_category"], "Watch");
+
+        export_event(&mut present, DeviceEvent::Removed(device(0x00c0)));
+        assert!(series("bluetooth_device_info", address).is_empty());
+    }
+
+    #[test]
+    fn replaces_changed_device_class() {
+        let address = Address([0x00, 0x00, 0x00, 0x00, 0x01, 0x03]);
+        let mut present = devices::Present::default();
+        let device = |class| Device {
+            address,
+            class: Some(class),
+            ..Default::default()
+        };
+        // a phone that later reports itself as a headset
+        export_event(&mut present, DeviceEvent::Added(device(0x5a020c)));
+        let info = series("bluetooth_device_info", address);
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0]["major_class"], "Phone");
+        export_event(&mut present, DeviceEvent::Updated(device(0x240404)));
+
+        let info = series("bluetooth_device_info", address);
+        assert_eq!(info.len(), 1);
+        assert_eq!(info[0]["major_class"], "Audio/Video");
+        assert_eq!(info[0]["minor_class"], "Wearable Headset Device");
+
+        export_event(&mut present, DeviceEvent::Lost(device(0x240404)));
+        assert!(series("bluetooth_device_info", address).is_empty());
+    }
 }
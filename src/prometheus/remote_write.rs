@@ -1,39 +1,84 @@
-use std::time::UNIX_EPOCH;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 
-use crate::bluetooth::Device;
+use crate::bluetooth::{Device, DeviceEvent};
 use crate::config::HOSTNAME;
+use crate::decode::{self, Decoded, Reading};
 use crate::device_writer;
 
+use super::devices;
 use super::{
     metric_metadata::MetricType, Client, Label, MetricMetadata, Sample, TimeSeries, WriteRequest,
 };
 
+/// Prometheus staleness marker, used to end the series of devices that are gone.
+const STALE_NAN: u64 = 0x7ff0000000000002;
+
 #[derive(Debug, Clone)]
 struct Labels(Vec<Label>);
 
-impl From<Device> for Labels {
-    fn from(device: Device) -> Self {
+impl Labels {
+    fn new(device: &Device, decoded: &Decoded) -> Self {
         let mut labels = vec![
+            Label {
+                name: "adapter".to_owned(),
+                value: device.adapter.clone(),
+            },
             Label {
                 name: "address".to_owned(),
                 value: device.address.to_string(),
             },
+            Label {
+                name: "address_type".to_owned(),
+                value: decoded.address_kind.as_str().to_owned(),
+            },
             Label {
                 name: "host".to_owned(),
                 value: HOSTNAME.to_string(),
             },
         ];
 
-        if let Some(name) = device.name {
+        if let Some(identity) = &decoded.identity {
+            labels.push(Label {
+                name: "identity".to_owned(),
+                value: identity.clone(),
+            });
+        }
+        if let Some(name) = &device.name {
             labels.push(Label {
                 name: "name".to_owned(),
-                value: name,
+                value: name.clone(),
             });
         }
         Labels(labels)
     }
+
+    fn extend(&mut self, labels: Vec<(&'static str, String)>) {
+        self.0.extend(labels.into_iter().map(|(name, value)| Label {
+            name: name.to_owned(),
+            value,
+        }));
+    }
+}
+
+/// Milliseconds since the unix epoch, as Prometheus timestamps samples.
+fn millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Add the metadata of a metric family, once per request.
+fn add_metadata(req: &mut WriteRequest, metadata: MetricMetadata) {
+    let family = &metadata.metric_family_name;
+    if !req
+        .metadata
+        .iter()
+        .any(|md| &md.metric_family_name == family)
+    {
+        req.metadata.push(metadata);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +87,7 @@ where
     C: Client,
 {
     client: C,
+    present: devices::Present,
 }
 
 impl<C> RemoteWrite<C>
@@ -49,60 +95,83 @@ where
     C: Client,
 {
     pub fn new(client: C) -> RemoteWrite<C> {
-        Self { client }
+        Self {
+            client,
+            present: devices::Present::default(),
+        }
     }
 
-    fn get_rssi(&self, device: Device) -> (TimeSeries, MetricMetadata) {
-        let mut labels = Labels::from(device.clone());
-        labels.0.push(Label {
-            name: "__name__".to_owned(),
-            value: "bluetooth_rssi".to_owned(),
-        });
+    fn get_device_count(
+        &self,
+        metric: &str,
+        help: &str,
+        label: (&str, String),
+        count: usize,
+        timestamp: i64,
+    ) -> (TimeSeries, MetricMetadata) {
         let series = TimeSeries {
-            labels: labels.0,
+            labels: vec![
+                Label {
+                    name: "host".to_owned(),
+                    value: HOSTNAME.to_string(),
+                },
+                Label {
+                    name: label.0.to_owned(),
+                    value: label.1,
+                },
+                Label {
+                    name: "__name__".to_owned(),
+                    value: metric.to_owned(),
+                },
+            ],
             samples: vec![Sample {
-                timestamp: device
-                    .timestamp
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64,
-                value: device.rssi.unwrap_or(0) as f64,
+                timestamp,
+                value: count as f64,
             }],
             exemplars: vec![],
         };
         let metadata = MetricMetadata {
             r#type: MetricType::Gauge.into(),
-            metric_family_name: "bluetooth_rssi".to_owned(),
-            help: "The Received Signal Strength Indicator value for the bluetooth device."
-                .to_owned(),
-            unit: "RSSI".to_owned(),
+            metric_family_name: metric.to_owned(),
+            help: help.to_owned(),
+            unit: "".to_owned(),
         };
         (series, metadata)
     }
 
-    fn get_tx_power(&self, device: Device) -> (TimeSeries, MetricMetadata) {
-        let mut labels = Labels::from(device.clone());
+    fn get_reading(
+        &self,
+        device: &Device,
+        decoded: &Decoded,
+        reading: &Reading,
+    ) -> (TimeSeries, MetricMetadata) {
+        let mut labels = Labels::new(device, decoded);
+        // empty labels are the same as missing ones to Prometheus
+        labels.extend(
+            reading
+                .labels
+                .iter()
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(name, value)| (*name, value.clone()))
+                .collect(),
+        );
         labels.0.push(Label {
             name: "__name__".to_owned(),
-            value: "bluetooth_tx_power".to_owned(),
+            value: reading.name.to_owned(),
         });
         let series = TimeSeries {
             labels: labels.0,
             samples: vec![Sample {
-                timestamp: device
-                    .timestamp
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64,
-                value: device.tx_power.unwrap_or(0) as f64,
+                timestamp: millis(device.timestamp),
+                value: reading.value,
             }],
             exemplars: vec![],
         };
         let metadata = MetricMetadata {
             r#type: MetricType::Gauge.into(),
-            metric_family_name: "bluetooth_tx_power".to_owned(),
-            help: "The TX Power in dBm for the bluetooth device.".to_owned(),
-            unit: "dBm".to_owned(),
+            metric_family_name: reading.name.to_owned(),
+            help: reading.help.to_owned(),
+            unit: reading.unit.to_owned(),
         };
         (series, metadata)
     }
@@ -113,22 +182,75 @@ impl<C> device_writer::DeviceWriter for RemoteWrite<C>
 where
     C: Client + Send + Sync,
 {
-    async fn write(&mut self, device: Device) {
+    async fn write(&mut self, event: DeviceEvent, decoded: &Decoded) {
+        let device = event.device();
         let mut req = WriteRequest {
             timeseries: vec![],
             metadata: vec![],
         };
 
-        if device.rssi.is_some() {
-            let (ts, md) = self.get_rssi(device.clone());
-            req.timeseries.push(ts);
-            req.metadata.push(md);
+        if !decode::aggregated(device) {
+            for reading in decoded.readings(device) {
+                let (ts, md) = self.get_reading(device, decoded, &reading);
+                req.timeseries.push(ts);
+                add_metadata(&mut req, md);
+            }
+        }
+
+        // the time of the event, so replays are written at the recorded time
+        let timestamp = millis(device.timestamp);
+        if event.is_gone() {
+            req.timeseries
+                .iter_mut()
+                .flat_map(|series| series.samples.iter_mut())
+                .for_each(|sample| sample.value = f64::from_bits(STALE_NAN));
         }
 
-        if device.tx_power.is_some() {
-            let (ts, md) = self.get_tx_power(device);
+        // counted over all devices, so not ended with the device
+        let counts = self.present.count(&event, decoded);
+        for (manufacturer, count) in counts.by_manufacturer {
+            let (ts, md) = self.get_device_count(
+                "bluetooth_devices_by_manufacturer",
+                "The number of present bluetooth devices by manufacturer.",
+                ("manufacturer", manufacturer),
+                count,
+                timestamp,
+            );
+            req.timeseries.push(ts);
+            add_metadata(&mut req, md);
+        }
+        for (address_type, count) in counts.by_address_type {
+            let (ts, md) = self.get_device_count(
+                "bluetooth_devices_by_address_type",
+                "The number of present bluetooth devices by address type.",
+                ("address_type", address_type.to_owned()),
+                count,
+                timestamp,
+            );
+            req.timeseries.push(ts);
+            add_metadata(&mut req, md);
+        }
+        for (device_family, count) in counts.by_device_family {
+            let (ts, md) = self.get_device_count(
+                "bluetooth_devices_by_device_family",
+                "The number of present bluetooth devices by device family.",
+                ("device_family", device_family.to_owned()),
+                count,
+                timestamp,
+            );
+            req.timeseries.push(ts);
+            add_metadata(&mut req, md);
+        }
+        for (message_type, count) in counts.by_apple_message {
+            let (ts, md) = self.get_device_count(
+                "bluetooth_devices_by_apple_message",
+                "The number of present bluetooth devices sending an Apple Continuity message type.",
+                ("message_type", message_type),
+                count,
+                timestamp,
+            );
             req.timeseries.push(ts);
-            req.metadata.push(md);
+            add_metadata(&mut req, md);
         }
 
         self.client.remote_write(req).await;